@@ -0,0 +1,27 @@
+// Generates the list of built-in weapon definitions from assets/weapons, so that adding a file there is all it takes to add a weapon
+
+use std::path::Path;
+
+fn main() {
+    let weapons_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/weapons");
+    println!("cargo:rerun-if-changed={}", weapons_directory.display());
+
+    let mut entries: Vec<(String, String)> = Vec::new(); // Name and absolute path
+    for entry in std::fs::read_dir(&weapons_directory).expect("Couldn't read assets/weapons") {
+        let path = entry.unwrap().path();
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        if let Some(name) = file_name.strip_suffix(".weapon.ron") {
+            println!("cargo:rerun-if-changed={}", path.display());
+            entries.push((name.to_string(), path.display().to_string()));
+        }
+    }
+    entries.sort(); // read_dir order isn't guaranteed
+
+    let mut source = String::from("const BUILT_IN_WEAPON_DEFINITIONS: &[(&str, &str)] = &[\n");
+    for (name, path) in entries {
+        source += &format!("    ({:?}, include_str!({:?})),\n", name, path);
+    }
+    source += "];\n";
+    let out_path = Path::new(&std::env::var("OUT_DIR").unwrap()).join("built_in_weapon_definitions.rs");
+    std::fs::write(out_path, source).expect("Couldn't write built-in weapon definitions");
+}
@@ -1,6 +1,7 @@
 use std::f32::consts::TAU;
 use bevy::prelude::*;
 use extol_sprite_layer::LayerIndex;
+use serde::{Serialize, Deserialize};
 
 #[derive(Component)]
 pub struct Position {pub value: Vec2}
@@ -20,6 +21,12 @@ pub struct Gait {
     pub floored_recovery_time: f32 // How long in seconds from being floored (just recovered from flying, etc) to standing again
 }
 
+#[derive(Component)]
+pub struct LegInjury { // Slows walking on top of Gait, which stays as the uninjured speeds
+    pub speed_multiplier: f32, // For both max speeds, 1.0 when uninjured
+    pub minimum_speed_multiplier: f32 // However many leg hits, speed_multiplier doesn't go below this
+}
+
 pub const DEFAULT_REGROUND_THRESHOLD: f32 = 110.0;
 #[derive(Component)]
 pub struct RegroundThreshold {pub value: f32} // Flying to grounded under or at this speed
@@ -84,23 +91,89 @@ pub struct Gun {
     pub projectile_colour: Color,
     pub projectile_mass: f32,
     pub projectile_base_damage_per_unit: f32,
+    pub projectile_penetration_power: f32,
     pub cooldown: f32,
     pub auto: bool,
+    pub magazine_capacity: u32,
+    pub reload_time: f32,
+    pub hitscan: bool, // Resolves each shot's whole path instantly instead of spawning projectiles
+    pub projectile_explosive: Option<Explosive>, // Detonates where the shot stops
+    pub projectile_fuse_time: Option<f32>, // Detonates this long after firing if it hasn't hit anything
+    pub heat: Option<GunHeat>,
 
     pub cooldown_timer: f32,
     pub trigger_depressed: bool,
-    pub trigger_depressed_previous_frame: bool
+    pub trigger_depressed_previous_frame: bool,
+    pub rounds_in_magazine: u32, // Each shot uses one round, however many projectiles it fires
+    pub reserve_ammo: u32,
+    pub reload_timer: Option<f32>, // Some while reloading
+    pub heat_level: f32, // 0 to 1
+    pub overheat_timer: Option<f32>, // Some while cooling down after overheating
+    pub jammed: bool // Until reloaded
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GunHeat {
+    pub per_shot: f32, // Overheats at 1
+    pub dissipation_rate: f32, // Per second
+    pub spread_multiplier: f32, // Spread is multiplied by 1 + this * heat level
+    pub jam_chance: f32, // Per shot at full heat, scaling down linearly with heat level
+    pub overheat_cooldown: f32 // How long an overheated gun can't fire for
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explosive { // What happens on detonation
+    pub force: f32, // At the centre, falling off linearly to nothing at the radius
+    pub damage: f32, // Likewise
+    pub radius: f32
 }
 
+#[derive(Component)]
+pub struct Fuse {
+    pub time: f32, // From being lit to detonating
+    pub time_left: Option<f32>, // Some once lit
+    pub lit_by: Option<Entity>
+}
+
+#[derive(Component)]
+pub struct Weapon {pub name: String} // Its kind, for attribution. For guns, also which WeaponDefinition it was spawned from, so that changes to the definition can be applied to it
+
+#[derive(Component)]
+pub struct AmmoPickup {pub rounds: u32} // Goes into the reserve of the gun held by whoever picks it up
+
+#[derive(Component)]
+pub struct ArmourPickup {pub armour: Armour} // Worn by whoever picks it up, if they aren't wearing any already
+
 #[derive(Component)]
 pub struct TracedLine; // For rendering
 
+#[derive(Component)]
+pub struct Beam { // For rendering a hitscan shot's path, fading out
+    pub start: Vec2,
+    pub end: Vec2,
+    pub time_left: f32
+}
+
 #[derive(Component)]
 pub struct GunProjectile; // For collision detection
 
+#[derive(Component, Clone)]
+pub struct ProjectileSource { // For attributing what the projectile hits
+    pub shooter: Entity,
+    pub weapon: Entity,
+    pub weapon_name: Option<String>
+}
+
 #[derive(Component)]
 pub struct ProjectileColour {pub value: Color}
 
+#[derive(Component)]
+pub struct Penetration {
+    pub power: f32, // How much body thickness the projectile can pass through before stopping. Speed is lost in proportion
+    pub penetrated: Vec<Entity>, // Bodies already passed into, so that they aren't hit again on later ticks
+    pub inside: Vec<Entity> // Those of them it hasn't come out of yet, for putting the exit wound where and when it does
+}
+
 #[derive(Component)]
 pub struct SpawnedMidTick {pub when: f32} // From 0 to 1
 
@@ -117,23 +190,45 @@ pub const DEFAULT_RESTITUTION: f32 = 1.0;
 #[derive(Component)]
 pub struct Restitution {pub value: f32}
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Clone, Serialize, Deserialize)]
 pub struct Will {
     pub target_relative_velocity_multiplier: Option<Vec2>, // Should not lie outside the unit cirlce
     pub target_angular_velocity_multiplier: Option<f32>, // Should be -1 to 1
     pub drop: bool,
+    pub throw: bool,
+    pub melee: bool,
     pub pick_up: bool,
-    pub depress_trigger: bool
+    pub depress_trigger: bool,
+    pub reload: bool,
+    pub use_item: bool
 }
 
+#[derive(Component)]
+pub struct SimulationId {pub value: u64} // Unlike Entity, the same across runs with and without rendering (which spawns its own entities)
+
 #[derive(Component)]
 pub struct Holder {
-    pub pick_up_range: f32
+    pub pick_up_range: f32,
+    pub throw_strength: f32 // Impulse given to thrown items, so lighter ones go faster
 }
 
 #[derive(Component)]
 pub struct Holdable;
 
+#[derive(Component)]
+pub struct Melee { // Swinging whatever is held, or a fist, through an arc in front
+    pub reach: f32, // Beyond the edge of the collider, plus the held item's diameter
+    pub arc: f32, // Total angle swept, centred on the facing
+    pub swing_speed: f32, // Times the swung mass for the force of a hit
+    pub unarmed_mass: f32,
+    pub cooldown: f32,
+
+    pub cooldown_timer: f32
+}
+
+#[derive(Component)]
+pub struct Thrown {pub thrower: Entity} // Strikes what it hits (except its thrower) until it lands
+
 #[derive(Component)]
 pub struct HoldingInfo {
     pub held_distance: f32,
@@ -151,6 +246,7 @@ pub struct ContainedBlood {
 
     pub leak_rate: f32,
     pub amount: f32, // Area of blood pool when fully depleted in pixels
+    pub maximum_amount: f32, // Transfusions can't go above this
     pub drip_timer: f32,
     pub amount_to_drip: f32 // Changes each time the drip timer is reset so that leak_rate can be in terms of units per second when dripping
 }
@@ -203,11 +299,39 @@ impl LayerIndex for DisplayLayer {
 }
 
 pub struct Hit {
-    pub entry_point: Vec2, // Relative to Position
+    pub entry_point: Vec2, // In world space. Its offset from Position is what makes the force turn the entity
     pub force: Vec2, // Applied directly, entry_point's position has already made a difference to the force
     pub damage: f32,
     pub apply_force: bool, // False if it has already been applied by collision resolution (kinda spaghetti, other projects may have more proper systems)
-    pub blood_loss: f32
+    pub damage_type: DamageType,
+    pub source: Option<Entity>, // Who's responsible, eg the shooter, swinger, thrower or the body collided with
+    pub weapon: Option<Entity>, // What they did it with, eg the gun or the held or thrown item
+    pub weapon_name: Option<String>, // From its Weapon component. Still says what it was once the entity is gone, eg after a grenade goes off
+    pub blood_loss: f32, // Lost at once as globules
+    pub bleed_rate: f32, // Of the wound left behind, in blood per second. 0.0 for no wound
+    pub exit_wound: bool // Where a projectile came out. Its force is what was carried out, counted for gibbing but not applied
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DamageType {Ballistic, Blunt, Explosive}
+
+#[derive(Component)]
+pub struct LastDamage { // Kept up to date by process_hits so that deaths can be attributed, including bleeding out later on
+    pub source: Option<Entity>,
+    pub weapon: Option<Entity>,
+    pub weapon_name: Option<String>,
+    pub damage_type: Option<DamageType>,
+    pub time_since: f32 // Seconds since it was last updated
+}
+
+#[derive(Component, Clone)]
+pub struct Armour {
+    pub ballistic_absorption: f32, // Fractions of damage taken by the armour instead of the wearer
+    pub blunt_absorption: f32,
+    pub explosive_absorption: f32,
+    pub blood_loss_multiplier: f32, // For hits taken while it's worn
+    pub durability: f32, // Goes down by the damage absorbed. Removed when it runs out
+    pub shatter_threshold: f32 // A single hit absorbing at least this much breaks it outright
 }
 
 #[derive(Component)]
@@ -236,6 +360,40 @@ pub struct Health {
     pub current: f32 // Can go below zero
 }
 
+#[derive(Component)]
+pub struct Healing { // Used up by its holder
+    pub health: f32, // Restored, up to Health's maximum
+    pub blood: f32, // Transfused, up to ContainedBlood's maximum amount
+    pub stops_bleeding: bool, // Closes all wounds
+    pub mends_legs: bool, // Undoes LegInjury
+    pub use_time: f32, // In seconds. The holder can't fight meanwhile
+    pub use_timer: Option<f32> // Some while being used
+}
+
+pub struct Wound {pub bleed_rate: f32}
+
+#[derive(Component)]
+pub struct Wounds { // Their bleed rates summed are ContainedBlood's leak rate
+    pub value: Vec<Wound>,
+    pub clotting_rate: Option<f32> // If some, how much each wound's bleed rate goes down per second, until it stops and is removed
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BodyPart {Head, Torso, Arm, Leg}
+
+pub struct BodyRegion {
+    pub part: BodyPart,
+    pub centre_angle: f32, // Relative to the entity's Angle, so 0.0 is the front
+    pub half_width: f32, // Entry points within this angle either side of centre_angle are in the region
+    pub damage_multiplier: f32,
+    pub blood_loss_multiplier: f32,
+    pub gait_multiplier: f32, // LegInjury's speed multiplier is multiplied by this on every damaging hit, 1.0 for no slowing
+    pub drops_held_items: bool
+}
+
+#[derive(Component)]
+pub struct BodyRegions {pub value: Vec<BodyRegion>} // Checked in order, the first region containing a hit's entry point is used. Hits in no region are unmodified
+
 #[derive(Component)]
 pub struct BaseDamagePerSpeed {pub value: f32}
 
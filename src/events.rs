@@ -1,10 +1,31 @@
 use bevy::prelude::*;
+use crate::components::{Explosive, DamageType};
 
-pub struct Dropping {pub entity: Entity}
+pub struct Dropping {
+	pub entity: Entity,
+	pub thrown: bool
+}
 
-pub struct Death {pub entity: Entity}
+pub struct Death {
+	pub entity: Entity,
+	pub killer: Option<Entity>,
+	pub weapon: Option<Entity>,
+	pub weapon_name: Option<String>,
+	pub damage_type: Option<DamageType>,
+	pub gibbed: bool
+}
+
+pub struct DryFire {pub gun: Entity} // The trigger was pulled on an empty magazine or a jammed gun
 
 pub struct Gibbing {
 	pub entity: Entity,
 	pub total_impact: f32
 }
+
+pub struct Detonation {
+	pub position: Vec2,
+	pub explosive: Explosive,
+	pub source: Option<Entity>, // Passed on to the blast's hits
+	pub weapon: Option<Entity>,
+	pub weapon_name: Option<String>
+}
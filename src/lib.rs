@@ -0,0 +1,200 @@
+// This project may have TODOs in it
+
+pub mod components;
+pub mod events;
+pub mod resources;
+pub mod systems;
+pub mod util;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use extol_sprite_layer::SpriteLayerPlugin;
+use systems::*;
+use components::*;
+use events::*;
+use resources::*;
+
+pub const TICKS_PER_SECOND: f32 = 120.0;
+
+#[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
+pub enum PreUpdateSet {Main, CommandFlush}
+
+#[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
+pub struct Wills;
+#[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
+pub enum LinearAngular {Locomotion, ApplyVelocity, Friction}
+
+#[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ConsistentStateChecks;
+
+#[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
+pub enum RenderPreparationSet {CommandFlush, Main}
+
+pub struct SimulationPlugin { // Everything that runs on the fixed tick. Works with MinimalPlugins
+    pub seed: u64,
+    pub ticks_per_second: f32,
+    pub starting_scene: StartingScene
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(FixedTime::new_from_secs(1.0 / self.ticks_per_second))
+            .insert_resource(SimulationRng::new(self.seed))
+            .insert_resource(self.starting_scene)
+            .init_resource::<NextSimulationId>()
+            .init_resource::<SpatialHash>()
+            .init_resource::<TileMaterials>()
+            .init_resource::<WeaponDefinitions>()
+
+            .add_event::<Dropping>()
+            .add_event::<Death>()
+            .add_event::<DryFire>()
+            .add_event::<Gibbing>()
+            .add_event::<Detonation>()
+
+            .add_startup_systems(( // Chained for determinism
+                startup::spawn_player.run_if(startup::starting_scene_is_default),
+                startup::spawn_other.run_if(startup::starting_scene_is_default),
+                startup::spawn_dots.run_if(startup::starting_scene_is_default),
+                startup::spawn_tilemaps
+            ).chain())
+
+            .add_systems((
+                pre_update::store_previous_position,
+                pre_update::store_previous_angle,
+                pre_update::store_previous_trigger_depressed,
+                pre_update::remove_spawned_mid_tick,
+                pre_update::clear_wills,
+                pre_update::assign_simulation_ids,
+                pre_update::remove_destroyed_but_rendered_entities,
+                pre_update::remove_hits
+            ).in_set(PreUpdateSet::Main).before(PreUpdateSet::CommandFlush).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(apply_system_buffers.in_set(PreUpdateSet::CommandFlush).before(Wills).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(physics::rebuild_spatial_hash.after(PreUpdateSet::CommandFlush).before(Wills).in_schedule(CoreSchedule::FixedUpdate)) // For picking_up
+
+            .add_system(wills::ai.run_if(wills::not_playing_back_replay).in_set(Wills).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(wills::play_back_replay.run_if(wills::playing_back_replay).in_set(Wills).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(wills::record_wills.run_if(resource_exists::<ReplayRecorder>()).after(Wills).before(hierarchy::send_dropping_events).in_schedule(CoreSchedule::FixedUpdate))
+
+            .add_systems(( // Not paralellised
+                hierarchy::send_dropping_events,
+                hierarchy::picking_up
+            ).chain().after(Wills).before(LinearAngular::Locomotion).in_schedule(CoreSchedule::FixedUpdate))
+
+            .add_systems(( // Parallelised
+                locomotion::walking,
+                locomotion::turning
+            ).in_set(LinearAngular::Locomotion).before(LinearAngular::ApplyVelocity).in_schedule(CoreSchedule::FixedUpdate))
+
+            .add_systems(( // Parallelised
+                physics::apply_velocity,
+                physics::apply_angular_velocity
+            ).in_set(LinearAngular::ApplyVelocity).in_schedule(CoreSchedule::FixedUpdate))
+
+            // Not parallelised
+            .add_systems(( // Chained for determinism, hitscan shots and melee swings both add Hits, and process_hits depends on their order
+                explosives::tick_fuses, // Before the flush so that detect_hits doesn't see what they despawn
+                guns::tick_guns,
+                healing::use_healing_items,
+                melee::melee // After healing so that starting to use an item stops a swing on the same tick
+            ).chain().after(LinearAngular::ApplyVelocity).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(apply_system_buffers.after(melee::melee).before(physics::wall_collision).in_schedule(CoreSchedule::FixedUpdate)) // So that detect_hits sees projectiles spawned this tick, in case they're shot inside a collider
+            .add_systems((
+                physics::wall_collision,
+                physics::rebuild_spatial_hash, // Again, now that colliders have moved
+                guns::detect_hits,
+                explosives::explode,
+                physics::collision,
+                gore::blood_loss,
+                gore::manage_globules
+            ).chain().in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(apply_system_buffers.after(gore::manage_globules).before(physics::manage_flyers).in_schedule(CoreSchedule::FixedUpdate)) // So that despawned blood globules won't be acted on (panics otherwise)
+            .add_systems((
+                physics::manage_flyers,
+                physics::manage_flooreds
+            ).chain().before(LinearAngular::Friction).in_schedule(CoreSchedule::FixedUpdate))
+
+            .add_systems(( // Parallelised
+                physics::floor_friction,
+                physics::angular_friction
+            ).in_set(LinearAngular::Friction).in_schedule(CoreSchedule::FixedUpdate))
+
+            .add_systems(( // Not parallelised
+                physics::tripping,
+                damage::process_hits,
+                damage::bleeding,
+                damage::check_health_and_blood,
+                damage::dying,
+                hierarchy::handle_dropping,
+                gore::gibbing,
+                guns::despawn_stationary_projectiles,
+                guns::tick_beams
+            ).chain().before(ConsistentStateChecks).after(LinearAngular::Friction).in_schedule(CoreSchedule::FixedUpdate))
+
+            .add_system(wills::save_replay_on_exit.run_if(resource_exists::<ReplayRecorder>()).in_base_set(CoreSet::Last));
+
+        #[cfg(debug_assertions)]
+        app.add_systems((
+            physics::check_consistent_grounded_flying_state,
+            hierarchy::check_consistent_hierarchy_state
+        ).in_set(ConsistentStateChecks).in_schedule(CoreSchedule::FixedUpdate));
+    }
+}
+
+pub struct PlayerInputPlugin; // Needs Input<KeyCode>, so not part of SimulationPlugin
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<KeysPressedSinceLastTick>()
+            .add_system(wills::latch_key_presses.run_if(wills::not_playing_back_replay).in_base_set(CoreSet::PreUpdate).after(bevy::input::InputSystem)) // Only player_input empties the buffer
+            .add_system(wills::player_input.run_if(wills::not_playing_back_replay).in_set(Wills).in_schedule(CoreSchedule::FixedUpdate));
+    }
+}
+
+pub struct WeaponAssetsPlugin; // Needs AssetServer, so not part of SimulationPlugin. Replaces the built-in weapon definitions with the files in assets/weapons, including when they change. Not for replays, since the files aren't recorded
+
+impl Plugin for WeaponAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<WeaponDefinition>()
+            .init_asset_loader::<WeaponDefinitionLoader>()
+            .init_resource::<WeaponDefinitionHandles>()
+            .init_resource::<PendingWeaponDefinitions>()
+
+            .add_startup_system(weapon_definitions::load_weapon_definitions)
+            .add_system(weapon_definitions::stage_weapon_definition_changes)
+            .add_system(weapon_definitions::apply_weapon_definition_changes.in_set(PreUpdateSet::Main).before(PreUpdateSet::CommandFlush).in_schedule(CoreSchedule::FixedUpdate)); // Only between ticks, so that changes land at the same point in the simulation regardless of frame timing
+    }
+}
+
+pub struct RenderingPlugin; // Runs once per frame, interpolating between ticks
+
+impl Plugin for RenderingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(ShapePlugin)
+            .add_plugin(TilemapPlugin)
+            .add_plugin(SpriteLayerPlugin::<DisplayLayer>::default())
+
+            .insert_resource(ClearColor(Color::BLACK))
+
+            .add_startup_system(startup::spawn_camera)
+            .add_startup_system(startup::load_tilemap_texture.in_base_set(StartupSet::PostStartup)) // After the simulation's tilemaps have been spawned
+
+            .add_system(apply_system_buffers.in_set(RenderPreparationSet::CommandFlush).before(RenderPreparationSet::Main))
+            .add_systems((
+                graphics::hollow_flying,
+                graphics::fill_grounded,
+                graphics::tint_hot_guns,
+                graphics::follow_player,
+                graphics::update_transforms,
+                graphics::rebuild_traced_shape,
+                graphics::rebuild_beam_shape,
+                graphics::rebuild_collider_shape,
+                graphics::rebuild_blood_pool
+            ).in_set(RenderPreparationSet::Main));
+    }
+}
@@ -1,138 +1,179 @@
-// This project may have TODOs in it
-
-mod components;
-mod events;
-mod systems;
-mod util;
-
+use std::path::PathBuf;
 use bevy::prelude::*;
-use bevy_prototype_lyon::prelude::*;
-use bevy_ecs_tilemap::prelude::*;
-use extol_sprite_layer::SpriteLayerPlugin;
-use systems::*;
-use components::*;
-use events::*;
-
-fn main() {
-    #[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
-    enum PreUpdateSet {Main, CommandFlush}
-
-    #[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
-    struct Wills;
-    #[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
-    enum LinearAngular {Locomotion, ApplyVelocity, Friction}
+use shootre::*;
+use shootre::components::*;
+use shootre::resources::*;
+
+struct Arguments {
+    seed: Option<u64>,
+    record_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+    headless_ticks: Option<u32>
+}
 
-    #[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
-    struct ConsistentStateChecks;
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments {
+        seed: None,
+        record_path: None,
+        replay_path: None,
+        headless_ticks: None
+    };
+    let mut iterator = std::env::args().skip(1);
+    while let Some(argument) = iterator.next() {
+        match argument.as_str() {
+            "--seed" => {
+                let value = iterator.next().expect("--seed requires a value");
+                arguments.seed = Some(value.parse().expect("--seed must be an unsigned 64-bit integer"));
+            },
+            "--record" => {
+                arguments.record_path = Some(PathBuf::from(iterator.next().expect("--record requires a path")));
+            },
+            "--replay" => {
+                arguments.replay_path = Some(PathBuf::from(iterator.next().expect("--replay requires a path")));
+            },
+            "--headless" => {
+                let value = iterator.next().expect("--headless requires a tick count");
+                arguments.headless_ticks = Some(value.parse().expect("--headless must be given an unsigned 32-bit integer"));
+            },
+            _ => panic!("Unrecognised argument {}", argument)
+        }
+    }
+    if arguments.replay_path.is_some() && arguments.seed.is_some() {
+        panic!("--seed can't be used with --replay, the replay's seed is used");
+    }
+    return arguments;
+}
 
-    #[derive(SystemSet, Debug, Clone, Hash, Eq, PartialEq)]
-    enum RenderPreparationSet {CommandFlush, Main}
+fn main() {
+    let arguments = parse_arguments();
+    let replay_option = arguments.replay_path.as_ref().map(Replay::load);
+    let seed;
+    let ticks_per_second;
+    let starting_scene;
+    if let Some(replay) = &replay_option {
+        seed = replay.seed;
+        ticks_per_second = replay.ticks_per_second;
+        starting_scene = replay.starting_scene;
+    } else {
+        seed = arguments.seed.unwrap_or_else(rand::random);
+        ticks_per_second = TICKS_PER_SECOND;
+        starting_scene = StartingScene::Default;
+    }
+    println!("Seed: {}", seed); // So that any run can be reproduced with --seed
 
     let mut app = App::new();
 
-    app
-        .add_plugins(
-            DefaultPlugins
-            .set(ImagePlugin::default_nearest())
-        )
-        .add_plugin(ShapePlugin)
-        .add_plugin(TilemapPlugin)
-        .add_plugin(SpriteLayerPlugin::<DisplayLayer>::default())
-
-        .insert_resource(ClearColor(Color::BLACK))
-
-        .add_event::<Dropping>()
-        .add_event::<Death>()
-        .add_event::<Gibbing>()
-
-        .add_startup_systems(( // Chained for determinism
-            startup::spawn_camera,
-            startup::spawn_player,
-            startup::spawn_other,
-            startup::spawn_dots,
-            startup::spawn_tilemaps
-        ).chain())
-
-        .add_systems((
-            pre_update::store_previous_position,
-            pre_update::store_previous_angle,
-            pre_update::store_previous_trigger_depressed,
-            pre_update::remove_spawned_mid_tick,
-            pre_update::clear_wills,
-            pre_update::remove_destroyed_but_rendered_entities,
-            pre_update::remove_hits
-        ).in_set(PreUpdateSet::Main).before(PreUpdateSet::CommandFlush))
-        .add_system(apply_system_buffers.in_set(PreUpdateSet::CommandFlush).before(Wills))
-
-
-        .add_systems(( // Parallellised
-            wills::player_input,
-            wills::ai
-        ).in_set(Wills))
-
-        .add_systems(( // Not paralellised
-            hierarchy::send_dropping_events,
-            hierarchy::picking_up
-        ).chain().after(Wills).before(LinearAngular::Locomotion))
-
-        .add_systems(( // Parallelised
-            locomotion::walking,
-            locomotion::turning
-        ).in_set(LinearAngular::Locomotion).before(LinearAngular::ApplyVelocity))
-
-        .add_systems(( // Parallelised
-            physics::apply_velocity,
-            physics::apply_angular_velocity
-        ).in_set(LinearAngular::ApplyVelocity))
-
-        // Not parallelised
-        .add_system(guns::tick_guns.after(LinearAngular::ApplyVelocity))
-        .add_system(apply_system_buffers.after(guns::tick_guns).before(guns::detect_hits)) // So that detect_hits sees projectiles spawned this tick, in case they're shot inside a collider
-        .add_systems((
-            guns::detect_hits,
-            physics::collision,
-            gore::blood_loss,
-            gore::manage_globules
-        ).chain())
-        .add_system(apply_system_buffers.after(gore::manage_globules).before(physics::manage_flyers)) // So that despawned blood globules won't be acted on (panics otherwise)
-        .add_systems((
-            physics::manage_flyers,
-            physics::manage_flooreds
-        ).chain().before(LinearAngular::Friction))
-
-        .add_systems(( // Parallelised
-            physics::floor_friction,
-            physics::angular_friction
-        ).in_set(LinearAngular::Friction))
-
-        .add_systems(( // Not parallelised
-            physics::tripping,
-            damage::process_hits,
-            damage::check_health_and_blood,
-            damage::dying,
-            hierarchy::handle_dropping,
-            gore::gibbing,
-            guns::despawn_stationary_projectiles
-        ).chain().before(ConsistentStateChecks).after(LinearAngular::Friction).before(RenderPreparationSet::CommandFlush));
-
-
-    #[cfg(debug_assertions)]
-    app.add_systems((
-        physics::check_consistent_grounded_flying_state,
-        hierarchy::check_consistent_hierarchy_state
-    ).in_set(ConsistentStateChecks).before(RenderPreparationSet::CommandFlush));
-
-    app
-        .add_system(apply_system_buffers.in_set(RenderPreparationSet::CommandFlush).before(RenderPreparationSet::Main))
-        .add_systems((
-            graphics::hollow_flying,
-            graphics::fill_grounded,
-            graphics::follow_player,
-            graphics::update_transforms,
-            graphics::rebuild_traced_shape,
-            graphics::rebuild_collider_shape,
-            graphics::rebuild_blood_pool
-        ).in_set(RenderPreparationSet::Main));
+    if arguments.headless_ticks.is_some() {
+        app.add_plugins(MinimalPlugins);
+    } else {
+        // Weapon files aren't part of a replay and can change between recording and replaying, so replays stick to the built-in definitions
+        let hot_reload_weapons = arguments.record_path.is_none() && arguments.replay_path.is_none();
+        app
+            .add_plugins(
+                DefaultPlugins
+                .set(ImagePlugin::default_nearest())
+                .set(AssetPlugin {
+                    watch_for_changes: hot_reload_weapons, // For tuning weapons while the game runs
+                    ..default()
+                })
+            );
+        if hot_reload_weapons {
+            app.add_plugin(WeaponAssetsPlugin);
+        }
+        app
+            .add_plugin(PlayerInputPlugin)
+            .add_plugin(RenderingPlugin);
+    }
+    app.add_plugin(SimulationPlugin {
+        seed: seed,
+        ticks_per_second: ticks_per_second,
+        starting_scene: starting_scene
+    });
+
+    if let Some(replay) = replay_option {
+        app.insert_resource(ReplayPlayer {
+            replay: replay,
+            next_tick: 0
+        });
+    }
+    if let Some(record_path) = arguments.record_path {
+        app.insert_resource(ReplayRecorder {
+            path: record_path,
+            replay: Replay {
+                seed: seed,
+                ticks_per_second: ticks_per_second,
+                starting_scene: starting_scene,
+                ticks: Vec::new()
+            }
+        });
+    }
+
+    if let Some(headless_ticks) = arguments.headless_ticks {
+        app.setup();
+        app.update(); // Runs startup. No time has passed yet, so no ticks happen
+        for _ in 0..headless_ticks {
+            app.world.run_schedule(CoreSchedule::FixedUpdate);
+        }
+        if let Some(replay_recorder) = app.world.get_resource::<ReplayRecorder>() {
+            replay_recorder.replay.save(&replay_recorder.path);
+        }
+        print_world_state(&mut app.world);
+    } else {
+        app.run();
+    }
+}
 
-    app.run();
+fn print_world_state(world: &mut World) {
+    let mut query = world.query::<(
+        Entity,
+        Option<&SimulationId>,
+        &Position,
+        Option<&Velocity>,
+        Option<&Angle>,
+        Option<&Health>,
+        Option<&ContainedBlood>,
+        Option<&Alive>,
+        Option<&Flying>,
+        Option<&Children>
+    )>();
+    println!("Willed entities and items:");
+    for (entity, simulation_id_option, position, velocity_option, angle_option, health_option, contained_blood_option, alive_option, flying_option, children_option) in query.iter(world) {
+        if simulation_id_option.is_none() && !world.entity(entity).contains::<Holdable>() {
+            continue;
+        }
+        print!("{:?}", entity);
+        if let Some(simulation_id) = simulation_id_option {
+            print!(" (simulation ID {})", simulation_id.value);
+        }
+        print!(": position {}", position.value);
+        if let Some(velocity) = velocity_option {
+            print!(", velocity {}", velocity.value);
+        }
+        if let Some(angle) = angle_option {
+            print!(", angle {}", angle.value);
+        }
+        if let Some(health) = health_option {
+            print!(", health {}/{}", health.current, health.maximum);
+        }
+        if let Some(contained_blood) = contained_blood_option {
+            print!(", blood {}", contained_blood.amount);
+        }
+        if simulation_id_option.is_some() {
+            print!(", {}", if alive_option.is_some() {"alive"} else {"dead"});
+        }
+        print!(", {}", if flying_option.is_some() {"flying"} else {"grounded"});
+        if let Some(children) = children_option {
+            print!(", holding {:?}", children.iter().collect::<Vec<_>>());
+        }
+        println!();
+    }
+
+    let mut gib_query = world.query_filtered::<(), With<Gib>>();
+    let mut globule_query = world.query_filtered::<(), With<BloodGlobule>>();
+    let mut blood_pool_query = world.query::<&BloodPool>();
+    let mut projectile_query = world.query_filtered::<(), With<GunProjectile>>();
+    println!("Gibs: {}", gib_query.iter(world).count());
+    println!("Blood globules: {}", globule_query.iter(world).count());
+    println!("Blood pools: {} (total area {})", blood_pool_query.iter(world).count(), blood_pool_query.iter(world).map(|blood_pool| blood_pool.area).sum::<f32>());
+    println!("Projectiles: {}", projectile_query.iter(world).count());
 }
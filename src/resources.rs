@@ -0,0 +1,313 @@
+use std::path::PathBuf;
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use bevy::utils::HashMap;
+use bevy_ecs_tilemap::tiles::TileTextureIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+use crate::components::*;
+use crate::util::collision_detection;
+
+#[derive(Resource, Default)]
+pub struct KeysPressedSinceLastTick {pub value: Vec<KeyCode>} // Input<KeyCode>::just_pressed is per frame, and a frame may run zero or several ticks
+
+#[derive(Resource)]
+pub struct SimulationRng { // Separate streams so that, for example, an extra gib doesn't change the spread of every shot after it
+    pub weapons: StdRng,
+    pub gore: StdRng,
+    pub world_generation: StdRng
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        let mut seeder = StdRng::seed_from_u64(seed);
+        return Self {
+            weapons: StdRng::seed_from_u64(seeder.gen()),
+            gore: StdRng::seed_from_u64(seeder.gen()),
+            world_generation: StdRng::seed_from_u64(seeder.gen())
+        };
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct NextSimulationId {pub value: u64}
+
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartingScene {
+    Default,
+    Empty // Just the tilemaps, for tests to spawn into
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub ticks_per_second: f32,
+    pub starting_scene: StartingScene,
+    pub ticks: Vec<Vec<(u64, Will)>> // For each tick, every willed entity's SimulationId and its will
+}
+
+impl Replay {
+    pub fn load(path: &PathBuf) -> Self {
+        let string = std::fs::read_to_string(path).expect("Couldn't read replay file");
+        return ron::from_str(&string).expect("Couldn't parse replay file");
+    }
+
+    pub fn save(&self, path: &PathBuf) {
+        let string = ron::to_string(self).expect("Couldn't serialise replay");
+        std::fs::write(path, string).expect("Couldn't write replay file");
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    pub path: PathBuf,
+    pub replay: Replay
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub next_tick: usize
+}
+
+pub const SPATIAL_HASH_CELL_SIZE: f32 = 32.0;
+
+#[derive(Resource, Default)]
+pub struct SpatialHash { // Broadphase for colliders, rebuilt by physics::rebuild_spatial_hash. Positions may have moved since the last rebuild
+    pub cells: HashMap<IVec2, Vec<Entity>>
+}
+
+impl SpatialHash {
+    fn get_cell(position: Vec2) -> IVec2 {
+        return (position / SPATIAL_HASH_CELL_SIZE).floor().as_ivec2();
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2, radius: f32) {
+        // Into every cell that the circle's bounding box overlaps
+        let lower = Self::get_cell(position - radius);
+        let upper = Self::get_cell(position + radius);
+        for x in lower.x..=upper.x {
+            for y in lower.y..=upper.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    pub fn query_circle(&self, position: Vec2, radius: f32) -> Vec<Entity> {
+        // Returns candidates whose bounding boxes may overlap the circle's, sorted so that results don't depend on hash map order
+        let mut entities = Vec::new();
+        let lower = Self::get_cell(position - radius);
+        let upper = Self::get_cell(position + radius);
+        for x in lower.x..=upper.x {
+            for y in lower.y..=upper.y {
+                if let Some(cell) = self.cells.get(&IVec2::new(x, y)) {
+                    entities.extend(cell.iter());
+                }
+            }
+        }
+        entities.sort_unstable();
+        entities.dedup();
+        return entities;
+    }
+
+    pub fn query_line(&self, line_start: Vec2, line_end: Vec2) -> Vec<Entity> {
+        // Returns candidates in the cells that the line segment passes through
+        let mut entities = Vec::new();
+        for intersection in collision_detection::new_grid_raycast(
+            line_start, line_end, SPATIAL_HASH_CELL_SIZE, SPATIAL_HASH_CELL_SIZE, Vec2::ZERO
+        ) {
+            if let Some(cell) = self.cells.get(&IVec2::new(intersection.tile_x, intersection.tile_y)) {
+                entities.extend(cell.iter());
+            }
+        }
+        entities.sort_unstable();
+        entities.dedup();
+        return entities;
+    }
+
+    pub fn get_pairs(&self) -> Vec<(Entity, Entity)> {
+        // Every pair of entities that share a cell, each pair once, in a consistent order
+        let mut pairs = Vec::new();
+        for cell in self.cells.values() {
+            for (i, a) in cell.iter().enumerate() {
+                for b in cell[i + 1..].iter() {
+                    pairs.push((*a.min(b), *a.max(b)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        return pairs;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileMaterial {
+    pub restitution: f32, // Multiplied with that of colliders hitting the tile as a wall (capped at 1), so above 1 makes things bouncier
+    pub floor_friction_multiplier: f32, // Multiplies the FloorFriction of whatever is on the tile as a floor
+    pub projectile_penetrability: f32 // Fraction of its speed a projectile keeps after passing through the tile as a wall. 0 stops projectiles
+}
+
+#[derive(Resource)]
+pub struct TileMaterials { // Keyed by TileTextureIndex so that a level picks materials by picking tiles
+    pub value: HashMap<u32, TileMaterial>,
+    pub default: TileMaterial // For indices not in the table
+}
+
+impl TileMaterials {
+    pub fn get(&self, texture_index: &TileTextureIndex) -> &TileMaterial {
+        return self.value.get(&texture_index.0).unwrap_or(&self.default);
+    }
+}
+
+impl Default for TileMaterials {
+    fn default() -> Self {
+        let concrete = TileMaterial {
+            restitution: 1.0, // Leaves colliders' own as it is
+            floor_friction_multiplier: 1.0,
+            projectile_penetrability: 0.0
+        };
+        let mut value = HashMap::new();
+        value.insert(1, concrete.clone()); // Floor
+        value.insert(2, concrete.clone()); // Wall
+        value.insert(3, TileMaterial { // Rubber wall
+            restitution: 2.5,
+            floor_friction_multiplier: 2.0,
+            projectile_penetrability: 0.0
+        });
+        value.insert(4, TileMaterial { // Ice floor
+            restitution: 0.5,
+            floor_friction_multiplier: 0.1,
+            projectile_penetrability: 0.0
+        });
+        value.insert(5, TileMaterial { // Metal grating
+            restitution: 0.3,
+            floor_friction_multiplier: 1.0,
+            projectile_penetrability: 0.8
+        });
+        return Self {
+            value: value,
+            default: concrete
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "7d3e0f52-8c1b-4f7e-9a57-2b6c1e4d9f80"]
+pub struct WeaponDefinition { // An archetype loaded from assets/weapons/<name>.weapon.ron
+    pub collider_radius: f32,
+    pub mass: f32,
+    pub restitution: f32,
+    pub floor_friction: f32,
+    pub colour: Color,
+
+    pub projectile_speed: f32,
+    pub projectile_flying_recovery_rate: f32,
+    pub projectile_spread: Vec2,
+    pub projectile_count: u32,
+    pub muzzle_distance: f32,
+    pub projectile_colour: Color,
+    pub projectile_mass: f32,
+    pub projectile_base_damage_per_unit: f32,
+    pub projectile_penetration_power: f32,
+    pub cooldown: f32,
+    pub auto: bool,
+    pub magazine_capacity: u32,
+    pub reload_time: f32,
+    pub hitscan: bool,
+    pub projectile_explosive: Option<Explosive>,
+    pub projectile_fuse_time: Option<f32>,
+    pub heat: Option<GunHeat>,
+    pub starting_reserve_ammo: u32
+}
+
+impl WeaponDefinition {
+    pub fn new_gun(&self) -> Gun { // With a full magazine
+        return Gun {
+            projectile_speed: self.projectile_speed,
+            projectile_flying_recovery_rate: self.projectile_flying_recovery_rate,
+            projectile_spread: self.projectile_spread,
+            projectile_count: self.projectile_count,
+            muzzle_distance: self.muzzle_distance,
+            projectile_colour: self.projectile_colour,
+            projectile_mass: self.projectile_mass,
+            projectile_base_damage_per_unit: self.projectile_base_damage_per_unit,
+            projectile_penetration_power: self.projectile_penetration_power,
+            cooldown: self.cooldown,
+            auto: self.auto,
+            magazine_capacity: self.magazine_capacity,
+            reload_time: self.reload_time,
+            hitscan: self.hitscan,
+            projectile_explosive: self.projectile_explosive.clone(),
+            projectile_fuse_time: self.projectile_fuse_time,
+            heat: self.heat.clone(),
+
+            cooldown_timer: 0.0,
+            trigger_depressed: false,
+            trigger_depressed_previous_frame: false,
+            rounds_in_magazine: self.magazine_capacity,
+            reserve_ammo: self.starting_reserve_ammo,
+            reload_timer: None,
+            heat_level: 0.0,
+            overheat_timer: None,
+            jammed: false
+        };
+    }
+}
+
+// Every file in assets/weapons, compiled in by build.rs so that the simulation doesn't need an AssetServer (headless runs, tests, replays).
+// Hot reloading replaces them and adds new files when rendering
+include!(concat!(env!("OUT_DIR"), "/built_in_weapon_definitions.rs"));
+
+#[derive(Resource)]
+pub struct WeaponDefinitions {pub value: HashMap<String, WeaponDefinition>} // Keyed by file name without extension
+
+impl WeaponDefinitions {
+    pub fn get(&self, name: &str) -> &WeaponDefinition {
+        return self.value.get(name).unwrap_or_else(|| panic!("No weapon definition named {}", name));
+    }
+}
+
+impl Default for WeaponDefinitions {
+    fn default() -> Self {
+        let mut value = HashMap::new();
+        for (name, source) in BUILT_IN_WEAPON_DEFINITIONS.iter() {
+            let definition: WeaponDefinition = ron::from_str(source).unwrap_or_else(|error| panic!("Couldn't parse built-in weapon definition {}: {}", name, error));
+            value.insert(name.to_string(), definition);
+        }
+        return Self {value: value};
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PendingWeaponDefinitions {pub value: Vec<(String, WeaponDefinition)>} // Loaded or changed files, held until the start of the next tick so that they don't land partway through one
+
+#[derive(Resource, Default)]
+pub struct WeaponDefinitionHandles {pub value: Vec<HandleUntyped>} // Kept so that the loaded definitions stay loaded and are watched for changes
+
+#[derive(Default)]
+pub struct WeaponDefinitionLoader;
+
+impl AssetLoader for WeaponDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        return Box::pin(async move {
+            let definition: WeaponDefinition = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(definition));
+            return Ok(());
+        });
+    }
+
+    fn extensions(&self) -> &[&str] {
+        return &["weapon.ron"];
+    }
+}
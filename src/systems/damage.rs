@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 use crate::components::*;
 use crate::events::*;
+use crate::resources::*;
+use crate::util::collision_resolution;
+use std::f32::consts::TAU;
 
 use super::gore::get_blood_transfer;
 use super::gore::spawn_blood_globules;
@@ -8,6 +11,30 @@ use super::gore::spawn_blood_globules;
 const GLOBULE_VELOCITY_VARIATION_MULTIPLIER: f32 = 0.2;
 const GLOBULE_SPEED: f32 = 100.0;
 const WOUND_BLOOD_LOSS_MAXIMUM: f32 = 50.0;
+const SOURCELESS_DAMAGE_CREDIT_TIME: f32 = 1.0; // How long after someone last did damage that hits from no one are still put down to them
+
+fn get_body_region<'a>(body_regions: &'a BodyRegions, position: Vec2, angle: f32, entry_point: Vec2) -> Option<&'a BodyRegion> { // Not a system
+	let offset = entry_point - position;
+	if offset == Vec2::ZERO {
+		return None;
+	}
+	let relative_angle = Vec2::from_angle(angle).angle_between(offset);
+	for body_region in body_regions.value.iter() {
+		let difference = (relative_angle - body_region.centre_angle + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0; // Wrapped to -TAU/2..TAU/2
+		if difference.abs() <= body_region.half_width {
+			return Some(body_region);
+		}
+	}
+	return None;
+}
+
+fn get_armour_absorption(armour: &Armour, damage_type: DamageType) -> f32 { // Not a system
+	return match damage_type {
+		DamageType::Ballistic => armour.ballistic_absorption,
+		DamageType::Blunt => armour.blunt_absorption,
+		DamageType::Explosive => armour.explosive_absorption
+	};
+}
 
 pub fn process_hits (
 	mut commands: Commands,
@@ -18,10 +45,22 @@ pub fn process_hits (
 		Option<&Mass>,
 		Option<&GibForceThreshold>,
 		Option<&mut ContainedBlood>,
-		Option<&mut Health>
+		Option<&mut Health>,
+		Option<&Position>,
+		Option<&Collider>,
+		Option<&mut AngularVelocity>,
+		Option<&Angle>,
+		Option<&BodyRegions>,
+		Option<&mut LegInjury>,
+		Option<&Children>,
+		(Option<&mut Wounds>, Option<&mut Armour>, Option<&mut LastDamage>, Option<&Alive>) // Nested to get around query size limit
 	)>,
+	child_query: Query<&HoldingInfo>,
 	mut die_event_writer: EventWriter<Death>,
-	mut gib_event_writer: EventWriter<Gibbing>
+	mut drop_event_writer: EventWriter<Dropping>,
+	mut gib_event_writer: EventWriter<Gibbing>,
+	mut rng: ResMut<SimulationRng>,
+	fixed_time: Res<FixedTime>
 ) {
 	for (
 		entity,
@@ -30,11 +69,24 @@ pub fn process_hits (
 		mass_option,
 		gib_force_threshold_option,
 		mut contained_blood_option,
-		mut health_option
+		mut health_option,
+		position_option,
+		collider_option,
+		mut angular_velocity_option,
+		angle_option,
+		body_regions_option,
+		mut leg_injury_option,
+		children_option,
+		(mut wounds_option, mut armour_option, mut last_damage_option, alive_option)
 	) in query.iter_mut() {
 		let mut to_die = false;
+		let mut to_drop_held_items = false;
 		let mut to_gib = false; // If any force is enough to cause gibbing, gib, but do it using the sum of all forces
 		let mut gib_total_impact = 0.0; // Add lengths of every force to be used in getting how many gibs to create
+		let mut gibbing_hit_option: Option<&Hit> = None; // The strongest, to attribute the death to
+		if let Some(last_damage) = last_damage_option.as_deref_mut() {
+			last_damage.time_since += fixed_time.period.as_secs_f32();
+		}
 
 		for hit in hits.value.iter() { //  The vector gets cleared at the beginnning of each frame
 			if let Some(mass) = mass_option {
@@ -42,30 +94,117 @@ pub fn process_hits (
 					velocity.value += hit.force / mass.value;
 				}
 				gib_total_impact += hit.force.length();
+
+				// Off-centre hits turn the entity. Not when the force has already been applied, since collision resolution turns things itself,
+				// and exit wounds' force left with the projectile
+				if let (Some(position), Some(collider), Some(angular_velocity)) = (position_option, collider_option, angular_velocity_option.as_deref_mut()) {
+					let moment_of_inertia = collision_resolution::get_moment_of_inertia(mass.value, collider.radius);
+					if moment_of_inertia > 0.0 && hit.apply_force {
+						angular_velocity.value += (hit.entry_point - position.value).perp_dot(hit.force) / moment_of_inertia;
+					}
+				}
 			}
 			if let Some(gib_force_threshold_component) = gib_force_threshold_option {
 				if hit.force.length() >= gib_force_threshold_component.value {
 					to_gib = true;
+					if gibbing_hit_option.map_or(true, |gibbing_hit| hit.force.length() > gibbing_hit.force.length()) {
+						gibbing_hit_option = Some(hit);
+					}
+				}
+			}
+
+			// Find where on the body the hit landed
+			let body_region_option;
+			if let (Some(body_regions), Some(position)) = (body_regions_option, position_option) {
+				let angle;
+				if let Some(angle_component) = angle_option {
+					angle = angle_component.value;
+				} else {
+					angle = 0.0;
+				}
+				body_region_option = get_body_region(body_regions, position.value, angle, hit.entry_point);
+			} else {
+				body_region_option = None;
+			}
+			let mut damage;
+			let mut blood_loss;
+			let mut bleed_rate;
+			if let Some(body_region) = body_region_option {
+				damage = hit.damage * body_region.damage_multiplier;
+				blood_loss = hit.blood_loss * body_region.blood_loss_multiplier;
+				bleed_rate = hit.bleed_rate * body_region.blood_loss_multiplier;
+				if damage > 0.0 {
+					if body_region.drops_held_items {
+						to_drop_held_items = true;
+					}
+					if let Some(leg_injury) = leg_injury_option.as_deref_mut() {
+						leg_injury.speed_multiplier = (leg_injury.speed_multiplier * body_region.gait_multiplier).max(leg_injury.minimum_speed_multiplier);
+					}
+				}
+			} else {
+				damage = hit.damage;
+				blood_loss = hit.blood_loss;
+				bleed_rate = hit.bleed_rate;
+			}
+
+			// Armour takes some of the damage, wearing down, and lessens bleeding while it lasts
+			if let Some(armour) = armour_option.as_deref_mut() {
+				if armour.durability > 0.0 {
+					let absorbed = (damage * get_armour_absorption(armour, hit.damage_type)).min(armour.durability);
+					damage -= absorbed;
+					blood_loss *= armour.blood_loss_multiplier;
+					bleed_rate *= armour.blood_loss_multiplier;
+					if absorbed >= armour.shatter_threshold {
+						armour.durability = 0.0;
+					} else {
+						armour.durability -= absorbed;
+					}
+				}
+			}
+
+			// Remember who did it. Hits from no one, like slamming into a wall, leave whoever sent the entity flying responsible, but only for a short while
+			if damage > 0.0 || bleed_rate > 0.0 {
+				if let Some(last_damage) = last_damage_option.as_deref_mut() {
+					if hit.source.is_some() || last_damage.source.is_none() || last_damage.time_since > SOURCELESS_DAMAGE_CREDIT_TIME {
+						last_damage.source = hit.source;
+						last_damage.weapon = hit.weapon;
+						last_damage.weapon_name = hit.weapon_name.clone();
+						last_damage.damage_type = Some(hit.damage_type);
+						last_damage.time_since = 0.0;
+					}
 				}
 			}
 
 			// Take damage from hit
 			if let Some(mut health_component) = health_option.as_deref_mut() {
-				health_component.current -= hit.damage;
+				health_component.current -= damage;
+			}
+
+			// Keep bleeding from hit
+			if bleed_rate > 0.0 {
+				if let Some(wounds) = wounds_option.as_deref_mut() {
+					wounds.value.push(Wound {bleed_rate: bleed_rate});
+				}
 			}
 
 			// Lose blood from hit
-			if hit.blood_loss > 0.0 && contained_blood_option.is_some() {
+			if blood_loss > 0.0 && contained_blood_option.is_some() {
 				let contained_blood = contained_blood_option.as_deref_mut().unwrap();
 				let blood_transfer = get_blood_transfer(
 					contained_blood.amount,
 					contained_blood.minimum_amount,
-					hit.blood_loss
+					blood_loss
 				).min(WOUND_BLOOD_LOSS_MAXIMUM);
 				contained_blood.amount -= blood_transfer;
-				let globule_velocity = velocity.value - hit.force.normalize_or_zero() * GLOBULE_SPEED;
+				// Blood sprays back out of entry wounds and onwards out of exit wounds
+				let globule_velocity = if hit.exit_wound {
+					velocity.value + hit.force.normalize_or_zero() * GLOBULE_SPEED
+				} else {
+					velocity.value - hit.force.normalize_or_zero() * GLOBULE_SPEED
+				};
 				spawn_blood_globules(
 					&mut commands,
+					&mut rng.gore,
 					3,
 					globule_velocity.length() * GLOBULE_VELOCITY_VARIATION_MULTIPLIER,
 					blood_transfer,
@@ -76,24 +215,82 @@ pub fn process_hits (
 			}
 		}
 
+		if let Some(armour) = armour_option {
+			if armour.durability <= 0.0 {
+				commands.entity(entity).remove::<Armour>();
+			}
+		}
+
+		if to_drop_held_items {
+			if let Some(children) = children_option {
+				for child_entity in children {
+					if let Ok(_) = child_query.get(*child_entity) {
+						drop_event_writer.send(Dropping {
+							entity: *child_entity,
+							thrown: false
+						});
+					}
+				}
+			}
+		}
+
 		if to_gib {
-			to_die = true;
+			to_die = alive_option.is_some(); // Corpses can be gibbed but not killed again
 			gib_event_writer.send(Gibbing {
 				entity: entity,
 				total_impact: gib_total_impact
 			});
 		}
 		if to_die {
-			die_event_writer.send(Death {entity: entity});
+			let gibbing_hit = gibbing_hit_option.unwrap();
+			if let (None, Some(last_damage)) = (gibbing_hit.source, last_damage_option.as_deref()) {
+				die_event_writer.send(Death {
+					entity: entity,
+					killer: last_damage.source,
+					weapon: last_damage.weapon,
+					weapon_name: last_damage.weapon_name.clone(),
+					damage_type: Some(gibbing_hit.damage_type),
+					gibbed: true
+				});
+			} else {
+				die_event_writer.send(Death {
+					entity: entity,
+					killer: gibbing_hit.source,
+					weapon: gibbing_hit.weapon,
+					weapon_name: gibbing_hit.weapon_name.clone(),
+					damage_type: Some(gibbing_hit.damage_type),
+					gibbed: true
+				});
+			}
+		}
+	}
+}
+
+pub fn bleeding(
+	mut query: Query<(&mut Wounds, &mut ContainedBlood)>,
+	fixed_time: Res<FixedTime>
+) {
+	for (mut wounds, mut contained_blood) in query.iter_mut() {
+		if let Some(clotting_rate) = wounds.clotting_rate {
+			for wound in wounds.value.iter_mut() {
+				wound.bleed_rate = (wound.bleed_rate - clotting_rate * fixed_time.period.as_secs_f32()).max(0.0);
+			}
+			wounds.value.retain(|wound| wound.bleed_rate > 0.0);
 		}
+		contained_blood.leak_rate = wounds.value.iter().map(|wound| wound.bleed_rate).sum();
 	}
 }
 
 pub fn check_health_and_blood(
 	mut die_event_writer: EventWriter<Death>,
-	query: Query<(Entity, Option<&Health>, Option<&ContainedBlood>), (With<Alive>, Without<Dead>)>
+	mut gib_events: EventReader<Gibbing>,
+	query: Query<(Entity, Option<&Health>, Option<&ContainedBlood>, Option<&LastDamage>), (With<Alive>, Without<Dead>)>
 ) {
-	for (entity, health_option, contained_blood_option) in query.iter() {
+	let gibbed: Vec<Entity> = gib_events.iter().map(|event| event.entity).collect(); // Their deaths have already been sent by process_hits
+	for (entity, health_option, contained_blood_option, last_damage_option) in query.iter() {
+		if gibbed.contains(&entity) {
+			continue;
+		}
 		let mut to_die = false;
 		if let Some(health) = health_option {
 			if health.current <= 0.0 {
@@ -108,7 +305,25 @@ pub fn check_health_and_blood(
 			}
 		}
 		if to_die {
-			die_event_writer.send(Death {entity: entity});
+			if let Some(last_damage) = last_damage_option {
+				die_event_writer.send(Death {
+					entity: entity,
+					killer: last_damage.source,
+					weapon: last_damage.weapon,
+					weapon_name: last_damage.weapon_name.clone(),
+					damage_type: last_damage.damage_type,
+					gibbed: false
+				});
+			} else {
+				die_event_writer.send(Death {
+					entity: entity,
+					killer: None,
+					weapon: None,
+					weapon_name: None,
+					damage_type: None,
+					gibbed: false
+				});
+			}
 		}
 	}
 }
@@ -133,7 +348,10 @@ pub fn dying(
 		if let Ok(children) = children_query.get(event.entity) {
 			for child_entity in children {
 				if let Ok(_) = child_query.get(*child_entity) {
-					drop_event_writer.send(Dropping {entity: *child_entity});
+					drop_event_writer.send(Dropping {
+						entity: *child_entity,
+						thrown: false
+					});
 				}
 			}
 		}
@@ -0,0 +1,195 @@
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::util::*;
+use crate::util::collision_detection;
+use crate::systems::startup::{TILEMAP_OFFSET, TILE_SIZE};
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+const BLAST_BLOOD_LOSS_PER_FORCE: f32 = 0.001;
+const BLAST_MAX_PUSH_SPEED: f32 = 3000.0; // Limits the speed given to things pushed without a hit, so that light ones aren't flung absurdly fast
+
+pub fn tick_fuses(
+    mut commands: Commands,
+    // Previous position/angle for the same reason as in tick_guns, and because the current ones haven't had wall collision yet
+    mut fuse_query: Query<(
+        Entity,
+        &mut Fuse,
+        &Explosive,
+        Option<&PreviousPosition>,
+        Option<&Parent>,
+        Option<&HoldingInfo>,
+        Option<&ProjectileSource>,
+        Option<&Weapon>
+    )>,
+    holder_query: Query<(Option<&Will>, Option<&Alive>, &PreviousPosition, Option<&PreviousAngle>), With<Children>>,
+    fixed_time: Res<FixedTime>,
+    mut detonation_event_writer: EventWriter<Detonation>
+) {
+    for (
+        entity,
+        mut fuse,
+        explosive,
+        previous_position_option,
+        parent_option,
+        holding_info_option,
+        projectile_source_option,
+        weapon_option
+    ) in fuse_query.iter_mut() {
+        // Held explosives are lit by their holder pulling the trigger
+        if fuse.time_left.is_none() {
+            if let Some(parent) = parent_option {
+                if let Ok((Some(will), Some(_), _, _)) = holder_query.get(parent.get()) {
+                    if will.depress_trigger {
+                        fuse.time_left = Some(fuse.time);
+                        fuse.lit_by = Some(parent.get());
+                    }
+                }
+            }
+        }
+        if fuse.time_left.is_none() {
+            continue;
+        }
+
+        let time_left = fuse.time_left.unwrap() - fixed_time.period.as_secs_f32();
+        if time_left > 0.0 {
+            fuse.time_left = Some(time_left);
+            continue;
+        }
+
+        let position;
+        if let Some(parent) = parent_option {
+            let (_, _, parent_previous_position, parent_previous_angle_option) = holder_query.get(parent.get()).unwrap();
+            let parent_previous_angle = if let Some(parent_previous_angle_component) = parent_previous_angle_option {parent_previous_angle_component.value} else {0.0};
+            position = parent_previous_position.value + Vec2::from_angle(parent_previous_angle).rotate(Vec2::new(holding_info_option.unwrap().held_distance, 0.0));
+        } else {
+            position = previous_position_option.unwrap().value; // Expected on the entity itself if there's no parent
+        }
+        // Launched ones belong to their shooter, hand-held ones to whoever lit them
+        let (source, weapon, weapon_name) = if let Some(projectile_source) = projectile_source_option {
+            (Some(projectile_source.shooter), Some(projectile_source.weapon), projectile_source.weapon_name.clone())
+        } else {
+            (fuse.lit_by, Some(entity), weapon_option.map(|weapon| weapon.name.clone()))
+        };
+        detonation_event_writer.send(Detonation {
+            position: position,
+            explosive: explosive.clone(),
+            source: source,
+            weapon: weapon,
+            weapon_name: weapon_name
+        });
+        commands.entity(entity).despawn_recursive(); // Recursive so that a holder's Children forgets it
+    }
+}
+
+fn get_blast_strength( // Not a system
+    // From 1 at the blast centre to 0 at its radius, measured to the nearest point of the body, and weakened or blocked by walls in the way
+    blast_position: Vec2,
+    blast_radius: f32,
+    target_position: Vec2,
+    target_radius: f32,
+    tilemap_query: &Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: &Query<&TileTextureIndex>,
+    tile_materials: &TileMaterials
+) -> f32 {
+    let surface_distance = (target_position.distance(blast_position) - target_radius).max(0.0);
+    if surface_distance >= blast_radius {
+        return 0.0;
+    }
+    let mut strength = 1.0 - surface_distance / blast_radius;
+
+    // Penetrable walls weaken it like they do projectiles
+    let (tile_size, tile_storage, tilemap_size) = tilemap_query.get_single().unwrap();
+    for intersection in collision_detection::new_grid_raycast(
+        blast_position, target_position, tile_size.x, tile_size.y, TILEMAP_OFFSET - TILE_SIZE / 2.0
+    ) {
+        if intersection.intersection_t <= 0.0 {
+            continue; // The tile the blast is in
+        }
+        let tile_position_option = tilemap::get_tile_position_from_grid(intersection.tile_x, intersection.tile_y, tilemap_size);
+        if tile_position_option.is_none() {
+            continue;
+        }
+        if let Some(tile_entity) = tile_storage.get(&tile_position_option.unwrap()) {
+            strength *= tile_materials.get(tile_query.get(tile_entity).unwrap()).projectile_penetrability;
+            if strength == 0.0 {
+                break;
+            }
+        }
+    }
+    return strength;
+}
+
+pub fn explode(
+    mut detonation_events: EventReader<Detonation>,
+    // Held items are left to their holders, who get pushed themselves
+    mut target_query: Query<(&Position, &Collider, Option<&mut Hits>, Option<(&mut Velocity, &Mass)>), Without<Parent>>,
+    mut projectile_query: Query<(&Position, &mut Velocity, &Mass), (With<GunProjectile>, Without<Collider>, Without<DestroyedButRender>)>,
+    tilemap_query: Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    spatial_hash: Res<SpatialHash>
+) {
+    for detonation in detonation_events.iter() {
+        let explosive = &detonation.explosive;
+        for candidate_entity in spatial_hash.query_circle(detonation.position, explosive.radius) {
+            let target_result = target_query.get_mut(candidate_entity);
+            if target_result.is_err() {
+                continue;
+            }
+            let (target_position, target_collider, target_hits_option, target_velocity_mass_option) = target_result.unwrap();
+            let strength = get_blast_strength(
+                detonation.position,
+                explosive.radius,
+                target_position.value,
+                target_collider.radius,
+                &tilemap_query,
+                &tile_query,
+                &tile_materials
+            );
+            if strength == 0.0 {
+                continue;
+            }
+
+            let direction = (target_position.value - detonation.position).normalize_or_zero();
+            let force = direction * explosive.force * strength;
+            // Things that can be hurt take it as a hit, which applies the force too. Anything else that can move is just pushed
+            if let Some(mut target_hits) = target_hits_option {
+                target_hits.value.push(Hit {
+                    entry_point: target_position.value - direction * target_collider.radius,
+                    force: force,
+                    damage: explosive.damage * strength,
+                    damage_type: DamageType::Explosive,
+                    source: detonation.source,
+                    weapon: detonation.weapon,
+                    weapon_name: detonation.weapon_name.clone(),
+                    apply_force: true,
+                    blood_loss: force.length() * BLAST_BLOOD_LOSS_PER_FORCE,
+                    bleed_rate: 0.0,
+                    exit_wound: false
+                });
+            } else if let Some((mut target_velocity, target_mass)) = target_velocity_mass_option {
+                target_velocity.value += (force / target_mass.value).clamp_length_max(BLAST_MAX_PUSH_SPEED);
+            }
+        }
+
+        // Projectiles in flight aren't in the spatial hash
+        for (projectile_position, mut projectile_velocity, projectile_mass) in projectile_query.iter_mut() {
+            let strength = get_blast_strength(
+                detonation.position,
+                explosive.radius,
+                projectile_position.value,
+                0.0,
+                &tilemap_query,
+                &tile_query,
+                &tile_materials
+            );
+            if strength == 0.0 {
+                continue;
+            }
+            let direction = (projectile_position.value - detonation.position).normalize_or_zero();
+            projectile_velocity.value += (direction * explosive.force * strength / projectile_mass.value).clamp_length_max(BLAST_MAX_PUSH_SPEED);
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use crate::components::*;
 use crate::util::*;
 use crate::events::*;
+use crate::resources::*;
 
 use rand::prelude::*;
+use rand::rngs::StdRng;
 use std::f32::consts::TAU;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
@@ -19,6 +21,7 @@ const GIB_VELOCITY_VARIATION_MULTIPLIER: f32 = 1.05;
 const GIBS_PER_GIB_FORCE_THRESHOLD_IN_GIB_TOTAL_IMPACT: f32 = 400.0;
 const MAX_GIBS_PER_GIBBING: u32 = 100;
 const GIB_LEAK_RATE_MULTIPLIER: f32 = 0.01; // Multiplied with blood amount, not leak rate
+const GIB_ANGULAR_VELOCITY_VARIATION: f32 = TAU * 2.0;
 
 pub fn gibbing(
 	mut commands: Commands,
@@ -29,9 +32,11 @@ pub fn gibbing(
 		Option<&ContainedBlood>,
 		&Position,
 		&Velocity,
+		Option<&AngularVelocity>,
 		Option<&Mass>,
 		Option<&Restitution>
-	)>
+	)>,
+	mut rng: ResMut<SimulationRng>
 ) {
 	for event in gib_events.iter() {
 		let (
@@ -40,6 +45,7 @@ pub fn gibbing(
 			contained_blood_option,
 			position,
 			velocity,
+			angular_velocity_option,
 			mass_option,
 			restitution_option
 		) = query.get(event.entity).unwrap();
@@ -53,6 +59,7 @@ pub fn gibbing(
 		};
 		gib(
 			&mut commands,
+			&mut rng.gore,
 			event.entity,
 			gib_count,
 			velocity.value.length() * GIB_VELOCITY_VARIATION_MULTIPLIER,
@@ -61,6 +68,7 @@ pub fn gibbing(
 			blood_colour,
 			position.value,
 			velocity.value,
+			if let Some(angular_velocity) = angular_velocity_option {angular_velocity.value} else {0.0},
 			match mass_option {
 				Some(mass_component) => {Some(mass_component.value)},
 				_ => {None}
@@ -75,6 +83,7 @@ pub fn gibbing(
 
 pub fn gib( // Not a system
 	commands: &mut Commands,
+	rng: &mut StdRng,
 	entity_to_gib: Entity,
 	gib_count: u32,
 	gib_velocity_variation: f32,
@@ -83,14 +92,14 @@ pub fn gib( // Not a system
 	blood_colour: Color,
 	position: Vec2,
 	velocity: Vec2,
+	angular_velocity: f32,
 	mass_option: Option<f32>,
 	restitution_option: Option<f32>
 ) {
-	// TODO: Factor in angular velocity
-	let mut rng = rand::thread_rng();
 	commands.entity(entity_to_gib).despawn();
 	for _ in 0..gib_count {
-		let gib_velocity = velocity + random_in_shape::circle(&mut rng, gib_velocity_variation);
+		let gib_velocity = velocity + random_in_shape::circle(rng, gib_velocity_variation);
+		let gib_angular_velocity = angular_velocity + rng.gen_range(-GIB_ANGULAR_VELOCITY_VARIATION..=GIB_ANGULAR_VELOCITY_VARIATION);
 		// Based on reground threshold, flying or floored is then added
 		let drip_time = 0.05;
 		let gib = commands.spawn((
@@ -102,7 +111,7 @@ pub fn gib( // Not a system
 			PreviousPosition {value: position},
 			Velocity {value: gib_velocity},
 			Angle {value: rng.gen_range(0.0..TAU)}, // TODO
-			AngularVelocity {value: 0.0}, // TODO
+			AngularVelocity {value: gib_angular_velocity},
 			Collider {
 				radius: area_to_radius(radius_to_area(radius) / gib_count as f32),
 				solid: false
@@ -117,6 +126,7 @@ pub fn gib( // Not a system
 
 				leak_rate: blood_amount * GIB_LEAK_RATE_MULTIPLIER,
 				amount: blood_amount / gib_count as f32,
+				maximum_amount: blood_amount / gib_count as f32,
 				drip_timer: 0.0,
 				amount_to_drip: drip_time // For the initial drip, act like the drip time was multiplied by 1, not something lower
 			},
@@ -151,6 +161,7 @@ const GLOBULE_LEAK_RATE: f32 = 0.5;
 
 pub fn spawn_blood_globules( // Not a system
 	commands: &mut Commands,
+	rng: &mut StdRng,
 	globule_count: u32,
 	globule_velocity_variation: f32,
 	blood_amount: f32,
@@ -158,9 +169,8 @@ pub fn spawn_blood_globules( // Not a system
 	position: Vec2,
 	velocity: Vec2
 ) {
-	let mut rng = rand::thread_rng();
 	for _ in 0..globule_count {
-		let globule_velocity = velocity + random_in_shape::circle(&mut rng, globule_velocity_variation);
+		let globule_velocity = velocity + random_in_shape::circle(rng, globule_velocity_variation);
 		let drip_time = 0.01;
 		let mut globule_commands = commands.spawn((
 			DisplayLayer {
@@ -184,6 +194,7 @@ pub fn spawn_blood_globules( // Not a system
 
 				leak_rate: GLOBULE_LEAK_RATE,
 				amount: blood_amount / globule_count as f32,
+				maximum_amount: blood_amount / globule_count as f32,
 				drip_timer: 0.0,
 				amount_to_drip: drip_time
 			},
@@ -244,9 +255,9 @@ pub fn blood_loss(
 	mut commands: Commands,
 	mut bleeder_query: Query<(&mut ContainedBlood, &Position, Option<&PreviousPosition>, Option<&Velocity>, Option<&Grounded>)>,
 	mut blood_pool_query: Query<(&mut BloodPool, &Position)>,
-	time: Res<Time>
+	fixed_time: Res<FixedTime>,
+	mut rng: ResMut<SimulationRng>
 ) {
-	let mut rng = rand::thread_rng();
 	for (mut contained_blood, position, previous_position_option, velocity_option, grounded_option) in bleeder_query.iter_mut() {
 		if contained_blood.amount == 0.0 || contained_blood.leak_rate == 0.0 || contained_blood.amount <= contained_blood.minimum_amount {
 			continue;
@@ -268,25 +279,25 @@ pub fn blood_loss(
 		let pooling = velocity.length() == 0.0; // Instead of dripping, just pool on the ground
 
 		// The timer operates regardless as to whether we're pooling or dripping
-		contained_blood.drip_timer -= time.delta_seconds();
+		contained_blood.drip_timer -= fixed_time.period.as_secs_f32();
 		if contained_blood.drip_timer <= 0.0 {
 			// If dripping, actually do something in the world with the drip timer reaching 0
 			if !pooling {
 				let blood_transfer = get_blood_transfer(
 					contained_blood.amount,
 					contained_blood.minimum_amount,
-					contained_blood.leak_rate * time.delta_seconds()
+					contained_blood.leak_rate * fixed_time.period.as_secs_f32()
 				);
 				contained_blood.amount -= blood_transfer;
 				spawn_blood_pool(
 					&mut commands,
 					blood_transfer,
-					previous_position.lerp(position.value, rng.gen_range(0.0..1.0)), // Lerped so that you don't see collected circles of blood drips in extreme hit-by-a-train gibbing scenarios
+					previous_position.lerp(position.value, rng.gore.gen_range(0.0..1.0)), // Lerped so that you don't see collected circles of blood drips in extreme hit-by-a-train gibbing scenarios
 					contained_blood.colour
 				);
 			}
 			// Reset timer. This comes after dripping because amount_to_drip from previous timer reset must be used before being overwritten
-			contained_blood.drip_timer = contained_blood.drip_time * rng.gen_range(contained_blood.drip_time_minimum_multiplier..=1.0); // Multiplied by random to stagger the drips
+			contained_blood.drip_timer = contained_blood.drip_time * rng.gore.gen_range(contained_blood.drip_time_minimum_multiplier..=1.0); // Multiplied by random to stagger the drips
 			if smearing {
 				contained_blood.drip_timer *= contained_blood.smear_drip_time_multiplier;
 			}
@@ -300,7 +311,7 @@ pub fn blood_loss(
 			let blood_transfer = get_blood_transfer(
 				contained_blood.amount,
 				contained_blood.minimum_amount,
-				contained_blood.leak_rate * time.delta_seconds()
+				contained_blood.leak_rate * fixed_time.period.as_secs_f32()
 			);
 			if blood_transfer > 0.0 {
 				contained_blood.amount -= blood_transfer;
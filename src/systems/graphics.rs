@@ -6,25 +6,45 @@ use bevy_prototype_lyon::prelude::*;
 pub const SCALE: f32 = 2.0;
 pub const PLAYER_DISTANCE_FROM_WINDOW_BOTTOM: f32 = 50.0;
 
+fn get_interpolation_lerp(fixed_time: &FixedTime) -> f32 {
+    // How far between the previous tick and the current tick we are, for interpolating spatial information when rendering
+    return (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.0);
+}
+
+fn interpolate_position(position: &Position, previous_position_option: Option<&PreviousPosition>, lerp: f32) -> Vec2 {
+    if let Some(previous_position) = previous_position_option {
+        return previous_position.value.lerp(position.value, lerp);
+    } else {
+        return position.value;
+    }
+}
+
+fn interpolate_angle(angle_option: Option<&Angle>, previous_angle_option: Option<&PreviousAngle>, lerp: f32) -> f32 {
+    if let Some(angle) = angle_option {
+        if let Some(previous_angle) = previous_angle_option {
+            return previous_angle.value * (1.0 - lerp) + angle.value * lerp;
+        } else {
+            return angle.value;
+        }
+    } else {
+        return 0.0;
+    }
+}
+
 pub fn follow_player(
     mut camera_query: Query<&mut Transform, With<Camera>>,
-    player_query: Query<
-        (&Position, Option<&Angle>),
-        (With<Player>, Or<(Changed<Position>, Changed<Angle>, Changed<Parent>)>)
-    >,
-    window_query: Query<&Window, With<bevy::window::PrimaryWindow>>
+    player_query: Query<(&Position, Option<&PreviousPosition>, Option<&Angle>, Option<&PreviousAngle>), With<Player>>,
+    window_query: Query<&Window, With<bevy::window::PrimaryWindow>>,
+    fixed_time: Res<FixedTime>
 ) {
     if let Ok(mut camera_transform) = camera_query.get_single_mut() {
-        if let Ok((player_position, player_angle_option)) = player_query.get_single() {
-            let entity_angle;
-            if let Some(angle) = player_angle_option {
-                entity_angle = angle.value;
-            } else {
-                entity_angle = 0.0;
-            }
+        if let Ok((player_position, player_previous_position_option, player_angle_option, player_previous_angle_option)) = player_query.get_single() {
+            let lerp = get_interpolation_lerp(&fixed_time);
+            let player_position = interpolate_position(player_position, player_previous_position_option, lerp); // Shadow
+            let entity_angle = interpolate_angle(player_angle_option, player_previous_angle_option, lerp);
             camera_transform.rotation = Quat::from_rotation_z(entity_angle - TAU / 4.0);
             let distance_to_move_player_down = (window_query.get_single().unwrap().height() / 2.0) / SCALE - PLAYER_DISTANCE_FROM_WINDOW_BOTTOM;
-            let camera_position = player_position.value + Vec2::from_angle(entity_angle) * distance_to_move_player_down; // Project camera position forwards to move player to bottom of screen
+            let camera_position = player_position + Vec2::from_angle(entity_angle) * distance_to_move_player_down; // Project camera position forwards to move player to bottom of screen
             let z_height = camera_transform.translation.z;
             camera_transform.translation = Vec3::new(camera_position.x, camera_position.y, z_height);
         }
@@ -32,25 +52,53 @@ pub fn follow_player(
 }
 
 pub fn update_transforms(
-    mut main_query: Query<
-        (&mut Transform, &DisplayLayer, Option<&Position>, Option<&Angle>, Option<&Parent>, Option<&HoldingInfo>, Option<&Flying>, Option<&Sprite>),
-        Or<(Added<UpdateTransforms>, Changed<Position>, Changed<Angle>, Changed<Parent>)>
-    >,
-    parent_query: Query<(&DisplayLayer, Option<&Flying>)>
+    // Not filtered by changes, since interpolation between ticks means transforms change every frame
+    mut main_query: Query<(
+        &mut Transform,
+        &DisplayLayer,
+        Option<&Position>,
+        Option<&PreviousPosition>,
+        Option<&Angle>,
+        Option<&PreviousAngle>,
+        Option<&Parent>,
+        Option<&HoldingInfo>,
+        Option<&Flying>,
+        Option<&Sprite>,
+        Option<&TracedLine>
+    )>,
+    parent_query: Query<(&DisplayLayer, Option<&Flying>)>,
+    fixed_time: Res<FixedTime>
 ) {
-    for (mut transform, display_layer, position_option, angle_option, parent_option, holding_info_option, flying_option, sprite_option) in main_query.iter_mut() {
+    let lerp = get_interpolation_lerp(&fixed_time);
+    for (
+        mut transform,
+        display_layer,
+        position_option,
+        previous_position_option,
+        angle_option,
+        previous_angle_option,
+        parent_option,
+        holding_info_option,
+        flying_option,
+        sprite_option,
+        traced_line_option
+    ) in main_query.iter_mut() {
         if parent_option.is_some() {
             let holding_info = holding_info_option.unwrap();
             transform.translation = Vec3::new(holding_info.held_distance, 0.0, 0.0);
             transform.rotation = Quat::from_rotation_z(holding_info.held_angle);
-        } else if let Some(position) = position_option {
+        } else if let Some(position_component) = position_option {
+            let position;
             let angle;
-            if let Some(angle_component) = angle_option {
-                angle = angle_component.value;
+            if traced_line_option.is_some() {
+                // Traced lines already draw the path between their previous and current positions
+                position = position_component.value;
+                angle = interpolate_angle(angle_option, None, lerp);
             } else {
-                angle = 0.0;
+                position = interpolate_position(position_component, previous_position_option, lerp);
+                angle = interpolate_angle(angle_option, previous_angle_option, lerp);
             }
-            transform.translation = Vec3::new(position.value.x, position.value.y, 0.0);
+            transform.translation = Vec3::new(position.x, position.y, 0.0);
             transform.rotation = Quat::from_rotation_z(angle);
         }
 
@@ -90,6 +138,25 @@ pub fn fill_grounded(mut query: Query<(&mut Fill, &Stroke), Added<Grounded>>) {
     }
 }
 
+const HOT_GUN_COLOUR: Color = Color::rgb(1.0, 0.3, 0.0);
+
+pub fn tint_hot_guns(mut query: Query<(&Gun, &mut Fill, &Stroke), Without<Flying>>) { // Flying ones are hollow
+    for (gun, mut fill, stroke) in query.iter_mut() {
+        if gun.heat.is_none() {
+            continue;
+        }
+        let cold = stroke.color.as_rgba_f32();
+        let hot = HOT_GUN_COLOUR.as_rgba_f32();
+        let heat_level = gun.heat_level;
+        fill.color = Color::rgba(
+            cold[0] + (hot[0] - cold[0]) * heat_level,
+            cold[1] + (hot[1] - cold[1]) * heat_level,
+            cold[2] + (hot[2] - cold[2]) * heat_level,
+            cold[3]
+        );
+    }
+}
+
 const DRAW_TRACER_AS_POINT_THRESHOLD: f32 = 1.0;
 const TRACER_POINT_CIRCLE_RADIUS: f32 = 0.1;
 
@@ -154,6 +221,20 @@ pub fn rebuild_traced_shape(
     }
 }
 
+const BEAM_FADE_TIME: f32 = 0.1;
+
+pub fn rebuild_beam_shape(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Beam, &mut Stroke, &ProjectileColour), With<Path>>
+) {
+    for (entity, beam, mut stroke, projectile_colour) in query.iter_mut() {
+        // Beams have no Position, so they're drawn in world space
+        let line = shapes::Line(beam.start, beam.end);
+        stroke.color.set_a(projectile_colour.value.a() * (beam.time_left / BEAM_FADE_TIME).min(1.0));
+        commands.entity(entity).insert(GeometryBuilder::build_as(&line));
+    }
+}
+
 pub fn rebuild_collider_shape(
     mut commands: Commands,
     query: Query<(Entity, &Collider), (Changed<Collider>, With<Path>)>
@@ -1,10 +1,17 @@
 use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
 use crate::util::*;
 use crate::util::collision_detection;
+use crate::util::collision_resolution;
 use crate::systems::startup::{TILEMAP_OFFSET, TILE_SIZE};
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+const RECOIL_GRIP_OFFSET: Vec2 = Vec2::new(0.0, -2.0); // Relative to the gun's angle, so guns are held slightly to the right
 
 fn progress_time_with_cooldown_interrupt(current: &mut f32, target: f32, cooldown: &mut f32) {
     // Move current up towards target but "stop" if cooldown ticks down towards 0 before then
@@ -20,46 +27,86 @@ pub fn tick_guns(
     // This is due to ordering in main.rs. Hopefully I won't come along and break this later. This could be better fixed with a more comprehensive
     // physics engine model, unless this is the right solution and I don't have the understanding to verify it as such.
     mut gun_query: Query<(
+        Entity,
         &mut Gun,
         Option<&Parent>,
         Option<&HoldingInfo>,
         Option<&PreviousPosition>,
         Option<&Velocity>,
         Option<&PreviousAngle>,
-        Option<&AngularVelocity>
+        Option<&AngularVelocity>,
+        Option<&Mass>,
+        Option<&Weapon>
     )>,
-    holder_query: Query<(
+    mut holder_query: Query<(
         Option<&Will>,
         Option<&Alive>,
         &PreviousPosition,
-        Option<&Velocity>,
+        Option<&mut Velocity>,
         Option<&PreviousAngle>,
-        Option<&AngularVelocity>
-    ), With<Children>>,
-    time: Res<Time>
+        Option<&mut AngularVelocity>,
+        Option<&Mass>,
+        Option<&Collider>
+    ), (With<Children>, Without<Gun>)>,
+    mut target_query: Query<(Entity, &Position, &PreviousPosition, &Collider, &mut Hits), Without<GunProjectile>>, // For hitscan
+    tilemap_query: Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    spatial_hash: Res<SpatialHash>,
+    fixed_time: Res<FixedTime>,
+    mut rng: ResMut<SimulationRng>,
+    mut dry_fire_event_writer: EventWriter<DryFire>,
+    mut detonation_event_writer: EventWriter<Detonation>
 ) {
     for (
+        gun_entity,
         mut gun,
         parent_option,
         holding_info_option,
         previous_position_option,
         velocity_option,
         previous_angle_option,
-        angular_velocity_option
+        angular_velocity_option,
+        gun_mass_option,
+        weapon_option
     ) in gun_query.iter_mut() {
-        // If no willed alive parent, trigger is not depressed, else trigger is depressed depending on will
+        // If no willed alive parent, trigger is not depressed and the gun isn't being reloaded, else they depend on will
         gun.trigger_depressed = false;
+        let mut reload_willed = false;
+        let mut held_by_willed_alive = false;
         if let Some(parent) = parent_option {
             let parent_result = holder_query.get(parent.get());
-            if let Ok((will_option, alive_option, _, _, _, _)) = parent_result {
+            if let Ok((will_option, alive_option, _, _, _, _, _, _)) = parent_result {
                 if let Some(will) = will_option {
                     if let Some(_) = alive_option {
                         gun.trigger_depressed = will.depress_trigger;
+                        reload_willed = will.reload;
+                        held_by_willed_alive = true;
                     }
                 }
             }
         }
 
+        // Reload, moving rounds from the reserve into the magazine when the reload time is up
+        if !held_by_willed_alive {
+            gun.reload_timer = None; // Dropping a gun abandons its reload
+        } else if reload_willed && gun.reload_timer.is_none() && (gun.jammed || (gun.rounds_in_magazine < gun.magazine_capacity && gun.reserve_ammo > 0)) {
+            gun.reload_timer = Some(gun.reload_time);
+        }
+        let reloading = gun.reload_timer.is_some();
+        if let Some(reload_timer) = gun.reload_timer {
+            let new_reload_timer = reload_timer - fixed_time.period.as_secs_f32();
+            if new_reload_timer <= 0.0 {
+                let rounds_loaded = (gun.magazine_capacity - gun.rounds_in_magazine).min(gun.reserve_ammo);
+                gun.rounds_in_magazine += rounds_loaded;
+                gun.reserve_ammo -= rounds_loaded;
+                gun.reload_timer = None;
+                gun.jammed = false; // Clearing a jam takes a reload
+            } else {
+                gun.reload_timer = Some(new_reload_timer);
+            }
+        }
+
         // Get spatial information from self or parent
         let position; // Based on previous position
         let velocity;
@@ -74,7 +121,9 @@ pub fn tick_guns(
                 parent_previous_position,
                 parent_velocity_option,
                 parent_previous_angle_option,
-                parent_angular_velocity_option
+                parent_angular_velocity_option,
+                _,
+                _
             )) = parent_result {
                 let holding_info = holding_info_option.unwrap();
                 let held_distance = holding_info.held_distance;
@@ -121,21 +170,44 @@ pub fn tick_guns(
             }
         }
 
+        // Heat dissipates over time, and an overheated gun can't fire until its forced cooldown is over
+        let heat_option = gun.heat.clone();
+        if let Some(heat) = &heat_option {
+            gun.heat_level = (gun.heat_level - heat.dissipation_rate * fixed_time.period.as_secs_f32()).max(0.0);
+            if let Some(overheat_timer) = gun.overheat_timer {
+                let new_overheat_timer = overheat_timer - fixed_time.period.as_secs_f32();
+                gun.overheat_timer = if new_overheat_timer <= 0.0 {None} else {Some(new_overheat_timer)};
+            }
+        }
+
+        let trigger_pulled = gun.trigger_depressed && !gun.trigger_depressed_previous_frame;
         let mut shoot = if gun.auto {
             gun.trigger_depressed
         } else {
-            gun.trigger_depressed && !gun.trigger_depressed_previous_frame
+            trigger_pulled
         };
-        let mut rng = rand::thread_rng();
+        if reloading {
+            shoot = false;
+        } else if shoot && (gun.rounds_in_magazine == 0 || gun.jammed) {
+            if trigger_pulled { // Once per pull, not every tick an automatic's trigger is held
+                dry_fire_event_writer.send(DryFire {gun: gun_entity});
+            }
+            shoot = false;
+        } else if gun.overheat_timer.is_some() {
+            shoot = false;
+        }
         // The key point here is that for rapid-fire guns, gun.cooldown (and
         // by extension gun.cooldown_timer) may fit in target_time multiple times
+        let mut recoil_impulse = Vec2::ZERO; // Summed over every projectile fired this tick
+        let mut recoil_angular_impulse = 0.0; // About the holder's centre
         let mut current_time = 0.0;
-        let target_time = time.delta_seconds();
+        let target_time = fixed_time.period.as_secs_f32();
         while current_time < target_time {
             progress_time_with_cooldown_interrupt(&mut current_time, target_time, &mut gun.cooldown_timer);
             if shoot && gun.cooldown_timer == 0.0 {
                 gun.cooldown_timer = gun.cooldown;
-                if !gun.auto { // Only once
+                gun.rounds_in_magazine -= 1;
+                if !gun.auto || gun.rounds_in_magazine == 0 { // Only once
                     shoot = false;
                 }
 
@@ -143,13 +215,48 @@ pub fn tick_guns(
                 let gun_angle = angle + angular_velocity * current_time;
                 let aim_direction = Vec2::from_angle(gun_angle);
                 let projectile_origin = gun_position + aim_direction * gun.muzzle_distance;
+                let spread_multiplier = if let Some(heat) = &heat_option {1.0 + heat.spread_multiplier * gun.heat_level} else {1.0};
+                let projectile_source_option = parent_option.map(|parent| ProjectileSource {
+                    shooter: parent.get(),
+                    weapon: gun_entity,
+                    weapon_name: weapon_option.map(|weapon| weapon.name.clone())
+                });
 
                 for _ in 0..gun.projectile_count {
                     // target_time - current_time is used a couple of times because the earlier the projectile was fired, the longer it has had for its properties to advance
                     let mut projectile_velocity = velocity + aim_direction * gun.projectile_speed +
-                        Vec2::from_angle(gun_angle).rotate(random_in_shape::circle(&mut rng, 1.0) * gun.projectile_spread * gun.projectile_speed); // In here because of projectile-specific use of random
+                        Vec2::from_angle(gun_angle).rotate(random_in_shape::circle(&mut rng.weapons, 1.0) * gun.projectile_spread * spread_multiplier * gun.projectile_speed); // In here because of projectile-specific use of random
                     let projectile_position = projectile_origin + projectile_velocity * (target_time - current_time); // TODO: collision detection for the distance travelled
 
+                    // The holder gets the opposite of the momentum the projectile gains relative to the gun.
+                    // Guns are gripped off the holder's centre line, so the recoil also turns them
+                    let projectile_impulse = (projectile_velocity - velocity) * gun.projectile_mass;
+                    recoil_impulse -= projectile_impulse;
+                    let grip_offset = Vec2::from_angle(gun_angle).rotate(RECOIL_GRIP_OFFSET);
+                    recoil_angular_impulse -= (projectile_origin + grip_offset - (position + velocity * current_time)).perp_dot(projectile_impulse);
+
+                    if gun.hitscan {
+                        fire_hitscan(
+                            &mut commands,
+                            projectile_origin,
+                            projectile_velocity,
+                            gun.projectile_mass,
+                            gun.projectile_base_damage_per_unit,
+                            gun.projectile_penetration_power,
+                            gun.projectile_colour,
+                            &mut target_query,
+                            &tilemap_query,
+                            &tile_query,
+                            &tile_materials,
+                            &spatial_hash,
+                            &mut rng.weapons,
+                            gun.projectile_explosive.as_ref(),
+                            &mut detonation_event_writer,
+                            projectile_source_option.as_ref()
+                        );
+                        continue;
+                    }
+
                     // Simulate a bit of speed reduction
                     let old_speed = projectile_velocity.length();
                     let flying_recovery_rate = gun.projectile_flying_recovery_rate;
@@ -160,145 +267,610 @@ pub fn tick_guns(
 
                     // No need to simulate collision detection here as it is done immediately using PreviousPosition
 
-                    commands.spawn((
-                        Position {value: projectile_position},
-                        PreviousPosition {value: projectile_origin},
-                        Velocity {value: projectile_velocity},
-                        Mass {value: gun.projectile_mass},
-                        ShapeBundle {..default()},
-                        Stroke::new(gun.projectile_colour, 1.0), // Gets immediately overwritten by a version with calculated alpha by rebuild_traced_shape
-                        ProjectileColour {value: gun.projectile_colour},
-                        Flying,
-                        FlyingRecoveryRate {value: flying_recovery_rate},
-                        TracedLine,
-                        GunProjectile,
-                        SpawnedMidTick {when: current_time / target_time},
-                        DisplayLayer {
-                            index: DisplayLayerIndex::Projectiles,
-                            flying: false
+                    spawn_projectile(
+                        &mut commands,
+                        projectile_position,
+                        projectile_origin,
+                        projectile_velocity,
+                        gun.projectile_mass,
+                        gun.projectile_colour,
+                        flying_recovery_rate,
+                        current_time / target_time,
+                        gun.projectile_base_damage_per_unit,
+                        Penetration {
+                            power: gun.projectile_penetration_power,
+                            penetrated: Vec::new(),
+                            inside: Vec::new()
                         },
-                        BaseDamagePerSpeed {value: gun.projectile_base_damage_per_unit}
-                    ));
+                        gun.projectile_explosive.clone(),
+                        gun.projectile_fuse_time.map(|fuse_time| fuse_time - (target_time - current_time)),
+                        projectile_source_option.clone()
+                    );
+                }
+
+                // Heat up, possibly overheating or jamming
+                if let Some(heat) = &heat_option {
+                    gun.heat_level = (gun.heat_level + heat.per_shot).min(1.0);
+                    if gun.heat_level >= 1.0 {
+                        gun.overheat_timer = Some(heat.overheat_cooldown);
+                        shoot = false;
+                    } else if rng.weapons.gen::<f32>() < heat.jam_chance * gun.heat_level {
+                        gun.jammed = true;
+                        shoot = false;
+                    }
                 }
             } else {
                 // If we're not shooting (or gun.cooldown_timer failed to reach 0 before current_time reached target_time)
                 break;
             }
         }
+
+        // Apply recoil to the holder, which moves and turns together with what it's holding
+        if recoil_impulse == Vec2::ZERO {
+            continue;
+        }
+        if let Some(parent) = parent_option {
+            if let Ok((
+                _,
+                _,
+                _,
+                parent_velocity_option,
+                _,
+                parent_angular_velocity_option,
+                parent_mass_option,
+                parent_collider_option
+            )) = holder_query.get_mut(parent.get()) {
+                if let Some(parent_mass) = parent_mass_option {
+                    let mut total_mass = parent_mass.value;
+                    if let Some(gun_mass) = gun_mass_option {
+                        total_mass += gun_mass.value;
+                    }
+                    if let Some(mut parent_velocity) = parent_velocity_option {
+                        parent_velocity.value += recoil_impulse / total_mass;
+                    }
+                    if let (Some(mut parent_angular_velocity), Some(parent_collider)) = (parent_angular_velocity_option, parent_collider_option) {
+                        parent_angular_velocity.value += recoil_angular_impulse / collision_resolution::get_moment_of_inertia(total_mass, parent_collider.radius);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn spawn_projectile( // Not a system
+    commands: &mut Commands,
+    position: Vec2,
+    previous_position: Vec2,
+    velocity: Vec2,
+    mass: f32,
+    colour: Color,
+    flying_recovery_rate: f32,
+    spawned_mid_tick: f32, // When in the tick the projectile was at previous_position, from 0 to 1
+    base_damage_per_speed: f32,
+    penetration: Penetration,
+    explosive_option: Option<Explosive>,
+    fuse_time_left_option: Option<f32>,
+    source_option: Option<ProjectileSource>
+) -> Entity {
+    let mut entity_commands = commands.spawn((
+        Position {value: position},
+        PreviousPosition {value: previous_position},
+        Velocity {value: velocity},
+        Mass {value: mass},
+        ShapeBundle {..default()},
+        Stroke::new(colour, 1.0), // Gets immediately overwritten by a version with calculated alpha by rebuild_traced_shape
+        ProjectileColour {value: colour},
+        Flying,
+        FlyingRecoveryRate {value: flying_recovery_rate},
+        TracedLine,
+        GunProjectile,
+        SpawnedMidTick {when: spawned_mid_tick},
+        DisplayLayer {
+            index: DisplayLayerIndex::Projectiles,
+            flying: false
+        },
+        BaseDamagePerSpeed {value: base_damage_per_speed},
+        penetration
+    ));
+    if let Some(explosive) = explosive_option {
+        entity_commands.insert(explosive);
+    }
+    if let Some(fuse_time_left) = fuse_time_left_option {
+        entity_commands.insert(Fuse {
+            time: fuse_time_left,
+            time_left: Some(fuse_time_left),
+            lit_by: None // Attributed through ProjectileSource instead
+        });
     }
+    if let Some(source) = source_option {
+        entity_commands.insert(source);
+    }
+    return entity_commands.id();
 }
 
 const PROJECTILE_BLOOD_LOSS_MULTIPLIER: f32 = 0.01;
+const PROJECTILE_BLEED_RATE_MULTIPLIER: f32 = 0.05; // Bullet holes keep bleeding
 const PROJECTILE_DAMAGE_MULTIPLIER: f32 = 1.0;
+const RICOCHET_MAX_INCIDENCE: f32 = 0.5; // Sine of the steepest angle between path and wall face that still ricochets, 30 degrees
+const RICOCHET_SPEED_KEPT_FRACTION: f32 = 0.6;
+const RICOCHET_SCATTER: f32 = 0.15; // Maximum random turn either way in radians
+const WALL_GAP: f32 = 0.01; // How far off a wall face ricochets start and detonations happen, so that they aren't inside the wall tile
+const MAX_RICOCHETS_PER_TICK: u32 = 4;
+const HITSCAN_RANGE: f32 = 1000.0;
+const BEAM_DURATION: f32 = 0.1;
 
-pub fn detect_hits( // TODO: Tilemap hits
-    mut commands: Commands,
-    mut projectile_query: Query<(Entity, &mut Position, &PreviousPosition, &Velocity, &Mass, &BaseDamagePerSpeed), (With<GunProjectile>, Without<DestroyedButRender>)>,
-    mut target_query: Query<(Entity, &Position, &Collider, &mut Hits), Without<GunProjectile>>,
-    tilemap_query: Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>
-) {
-    // Starts from previous position and goes to current position
-    for (
-        projectile_entity,
-        mut projectile_position,
-        projectile_previous_position,
-        projectile_velocity,
-        projectile_mass,
-        projectile_base_damage_per_speed
-    ) in projectile_query.iter_mut() {
-        let ray_start = projectile_previous_position.value;
-        let ray_end = projectile_position.value;
-        let mut ray_hit_t: Option<f32> = None;
-
-        // Limit the ray to the first hit on the tilemap
-        let (tile_size, tile_storage, tilemap_size) = tilemap_query.get_single().unwrap();
-        for intersection in collision_detection::new_grid_raycast(
-            ray_start, ray_end, tile_size.x, tile_size.y, TILEMAP_OFFSET - TILE_SIZE / 2.0
-        ) {
-            if !(
-                0 <= intersection.tile_x && (intersection.tile_x as u32) < tilemap_size.x &&
-                0 <= intersection.tile_y && (intersection.tile_y as u32) < tilemap_size.y
-            ) {
+enum RayEvent { // Things a projectile passes through, in order along its path
+    Tile {penetrability: f32},
+    Body {entity: Entity, exit_t: f32},
+    Exit {entity: Entity} // Out of a body it went into on an earlier tick
+}
+
+fn get_exit_wound_hit(exit_point: Vec2, exit_velocity: Vec2, mass: f32, source_option: Option<&ProjectileSource>) -> Hit { // Not a system
+    // The flesh torn through on the way out counts towards gibbing and bleeding
+    return Hit {
+        entry_point: exit_point,
+        force: exit_velocity * mass,
+        damage: 0.0,
+        damage_type: DamageType::Ballistic,
+        source: source_option.map(|source| source.shooter),
+        weapon: source_option.map(|source| source.weapon),
+        weapon_name: source_option.and_then(|source| source.weapon_name.clone()),
+        apply_force: false,
+        blood_loss: exit_velocity.length() * mass * PROJECTILE_BLOOD_LOSS_MULTIPLIER,
+        bleed_rate: exit_velocity.length() * mass * PROJECTILE_BLEED_RATE_MULTIPLIER,
+        exit_wound: true
+    };
+}
+
+fn trace_path( // Not a system
+    // Goes along one straight stretch of a projectile's path, adding Hits to bodies it passes into and losing speed and penetration power to them.
+    // Returns where along the stretch the projectile stopped, if it did, and the normal of the wall face it hit, if that's what stopped it
+    ray_start: Vec2,
+    ray_end: Vec2,
+    velocity: &mut Vec2,
+    power: &mut f32,
+    penetrated: &mut Vec<Entity>,
+    inside: &mut Vec<Entity>,
+    mass: f32,
+    base_damage_per_speed: f32,
+    target_query: &mut Query<(Entity, &Position, &PreviousPosition, &Collider, &mut Hits), Without<GunProjectile>>,
+    tilemap_query: &Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: &Query<&TileTextureIndex>,
+    tile_materials: &TileMaterials,
+    spatial_hash: &SpatialHash,
+    source_option: Option<&ProjectileSource>,
+    use_previous_positions: bool // For hitscan, which is resolved mid-tick, so bodies are traced where they were at the start of it like the spatial hash has them
+) -> (Option<f32>, Option<Vec2>) {
+    let mut ray_hit_t: Option<f32> = None; // Where the projectile stops, if it does
+    let mut wall_normal: Option<Vec2> = None;
+    let mut ray_events: Vec<(f32, RayEvent)> = Vec::new(); // Intersection t and what was entered
+
+    // Limit the ray to the first hit on an impenetrable tile, and note where it passes into penetrable ones
+    let (tile_size, tile_storage, tilemap_size) = tilemap_query.get_single().unwrap();
+    let mut previous_tile: Option<(i32, i32)> = None; // Grid coordinates of the last tile crossed, for working out which face was hit
+    for intersection in collision_detection::new_grid_raycast(
+        ray_start, ray_end, tile_size.x, tile_size.y, TILEMAP_OFFSET - TILE_SIZE / 2.0
+    ) {
+        let entered_from = previous_tile;
+        previous_tile = Some((intersection.tile_x, intersection.tile_y));
+        let tile_position_option = tilemap::get_tile_position_from_grid(intersection.tile_x, intersection.tile_y, tilemap_size);
+        if tile_position_option.is_none() {
+            continue;
+        }
+        let tile_option = tile_storage.get(&tile_position_option.unwrap());
+        if let Some(tile_entity) = tile_option {
+            let penetrability = tile_materials.get(tile_query.get(tile_entity).unwrap()).projectile_penetrability;
+            if penetrability > 0.0 {
+                if intersection.intersection_t > 0.0 { // Otherwise it was already paid for when the projectile entered the tile last tick
+                    ray_events.push((intersection.intersection_t, RayEvent::Tile {penetrability: penetrability}));
+                }
                 continue;
             }
-            let tile_option = tile_storage.get(&TilePos {x: intersection.tile_x as u32, y: intersection.tile_y as u32});
-            if let Some(_) = tile_option {
-                ray_hit_t = Some(intersection.intersection_t);
-                break;
+            ray_hit_t = Some(intersection.intersection_t);
+            // The ray steps one tile at a time, so whichever coordinate changed says which axis the face is on
+            if let Some((from_x, from_y)) = entered_from {
+                if intersection.tile_x != from_x {
+                    wall_normal = Some(Vec2::new(-((intersection.tile_x - from_x) as f32).signum(), 0.0));
+                } else if intersection.tile_y != from_y {
+                    wall_normal = Some(Vec2::new(0.0, -((intersection.tile_y - from_y) as f32).signum()));
+                }
             }
+            break;
         }
+    }
+
+    // Find where the projectile comes out of bodies it was already inside
+    for inside_entity in inside.clone().iter() {
+        let target_result = target_query.get(*inside_entity);
+        if target_result.is_err() {
+            inside.retain(|entity| entity != inside_entity); // Gone, eg gibbed
+            continue;
+        }
+        let (_, target_position, target_previous_position, target_collider, _) = target_result.unwrap();
+        let target_position = if use_previous_positions {target_previous_position.value} else {target_position.value};
+        let exit_t;
+        if !collision_detection::circle_point(target_collider.radius, target_position, ray_start) {
+            exit_t = 0.0; // The body moved off it between ticks
+        } else if let Some((_, intersection_out)) = collision_detection::line_circle_intersection(ray_start, ray_end, target_collider.radius, target_position) {
+            exit_t = intersection_out;
+        } else {
+            continue; // Not moving
+        }
+        if exit_t <= 1.0 {
+            ray_events.push((exit_t, RayEvent::Exit {entity: *inside_entity}));
+        }
+    }
 
-        // Get the closest entity on the potentially-truncated path
-        let mut hit_entity: Option<Entity> = None;
-        let mut hit_entry_wound: Option<Vec2> = None;
-        for (
+    // Get every body on the potentially-truncated path
+    for candidate_entity in spatial_hash.query_line(ray_start, ray_end) {
+        let target_result = target_query.get(candidate_entity);
+        if target_result.is_err() {
+            continue;
+        }
+        let (
             target_entity,
             target_position,
+            target_previous_position,
             target_collider,
             _
-        ) in target_query.iter_mut() {
-            if !target_collider.solid {
-                continue;
+        ) = target_result.unwrap();
+        let target_position = if use_previous_positions {target_previous_position.value} else {target_position.value};
+        if !target_collider.solid {
+            continue;
+        }
+        if penetrated.contains(&target_entity) {
+            continue; // Already went in on a previous tick or earlier in this one
+        }
+
+        let intersections_option = collision_detection::line_circle_intersection(
+            ray_start,
+            ray_end,
+            target_collider.radius,
+            target_position
+        );
+        if intersections_option.is_none() {
+            continue;
+        }
+        let (intersection_in, intersection_out) = intersections_option.unwrap();
+
+        let collision_t;
+        if collision_detection::circle_point(target_collider.radius, target_position, ray_start) {
+            // Hit from inside
+            collision_t = 0.0; // Filled circle
+        } else if 0.0 <= intersection_in && intersection_in <= 1.0 {
+            // Hit from outside
+            collision_t = intersection_in;
+        } else {
+            // Not a hit at this time
+            continue;
+        };
+
+        if ray_hit_t.is_none() || collision_t < ray_hit_t.unwrap() {
+            ray_events.push((collision_t, RayEvent::Body {
+                entity: target_entity,
+                exit_t: intersection_out
+            }));
+        }
+    }
+
+    // Go along the path, losing speed and penetration power to everything passed through until something stops the projectile
+    ray_events.sort_by(|(a_t, _), (b_t, _)| a_t.partial_cmp(b_t).unwrap());
+    let ray_length = ray_start.distance(ray_end);
+    for (t, ray_event) in ray_events.iter() {
+        if ray_hit_t.is_some() && *t >= ray_hit_t.unwrap() {
+            break;
+        }
+        match ray_event {
+            RayEvent::Tile {penetrability} => {
+                *velocity *= *penetrability;
+                *power *= *penetrability;
+            },
+            RayEvent::Body {entity, exit_t} => {
+                // Thickness is the length of the chord the projectile would cut through the body
+                let entry_point = ray_start.lerp(ray_end, *t);
+                let exit_point = ray_start.lerp(ray_end, *exit_t);
+                let thickness = ray_length * (exit_t - t);
+                let speed_kept_fraction = if *power > thickness {(*power - thickness) / *power} else {0.0};
+                let entry_velocity = *velocity;
+                let exit_velocity = entry_velocity * speed_kept_fraction;
+                let (_, _, _, _, mut target_hits) = target_query.get_mut(*entity).unwrap();
+                let source = source_option.map(|source| source.shooter);
+                let weapon = source_option.map(|source| source.weapon);
+
+                target_hits.value.push(Hit {
+                    entry_point: entry_point,
+                    force: (entry_velocity - exit_velocity) * mass, // Could take code from circle-circle collision resolution for this in a future project if it's more correct
+                    damage: entry_velocity.length() * base_damage_per_speed * PROJECTILE_DAMAGE_MULTIPLIER,
+                    damage_type: DamageType::Ballistic,
+                    source: source,
+                    weapon: weapon,
+                    weapon_name: source_option.and_then(|source| source.weapon_name.clone()),
+                    apply_force: true,
+                    blood_loss: entry_velocity.length() * mass * PROJECTILE_BLOOD_LOSS_MULTIPLIER,
+                    bleed_rate: entry_velocity.length() * mass * PROJECTILE_BLEED_RATE_MULTIPLIER,
+                    exit_wound: false
+                });
+
+                if speed_kept_fraction == 0.0 {
+                    ray_hit_t = Some(*t);
+                    wall_normal = None; // Stopped by a body before reaching the wall
+                    break;
+                }
+
+                // Out the other side, if it gets there before this stretch of the path ends. Otherwise on a later tick, or never if a wall stops it first
+                if *exit_t <= ray_hit_t.unwrap_or(1.0).min(1.0) {
+                    target_hits.value.push(get_exit_wound_hit(exit_point, exit_velocity, mass, source_option));
+                } else {
+                    inside.push(*entity);
+                }
+                *velocity = exit_velocity;
+                *power -= thickness;
+                penetrated.push(*entity);
+            },
+            RayEvent::Exit {entity} => {
+                let (_, _, _, _, mut target_hits) = target_query.get_mut(*entity).unwrap();
+                target_hits.value.push(get_exit_wound_hit(ray_start.lerp(ray_end, *t), *velocity, mass, source_option));
+                inside.retain(|inside_entity| inside_entity != entity);
             }
+        }
+    }
+    *power = power.max(0.0);
 
-            let intersections_option = collision_detection::line_circle_intersection(
-                projectile_previous_position.value,
-                projectile_position.value,
-                target_collider.radius,
-                target_position.value
-            );
-            if intersections_option.is_none() {
-                continue;
+    return (ray_hit_t, wall_normal);
+}
+
+fn get_ricochet_velocity(velocity: Vec2, wall_normal: Option<Vec2>, rng: &mut StdRng) -> Option<Vec2> { // Not a system
+    // Glancing wall hits bounce off, slower and a little scattered
+    let normal = wall_normal?;
+    let direction = velocity.normalize_or_zero();
+    if direction == Vec2::ZERO || direction.dot(normal).abs() >= RICOCHET_MAX_INCIDENCE {
+        return None;
+    }
+    let reflected_velocity = velocity - 2.0 * velocity.dot(normal) * normal;
+    let scatter = Vec2::from_angle(rng.gen_range(-RICOCHET_SCATTER..=RICOCHET_SCATTER));
+    return Some(scatter.rotate(reflected_velocity) * RICOCHET_SPEED_KEPT_FRACTION);
+}
+
+fn fire_hitscan( // Not a system
+    // Resolves a whole shot at once, leaving a beam along each stretch of its path
+    commands: &mut Commands,
+    origin: Vec2,
+    velocity: Vec2,
+    mass: f32,
+    base_damage_per_speed: f32,
+    penetration_power: f32,
+    colour: Color,
+    target_query: &mut Query<(Entity, &Position, &PreviousPosition, &Collider, &mut Hits), Without<GunProjectile>>,
+    tilemap_query: &Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: &Query<&TileTextureIndex>,
+    tile_materials: &TileMaterials,
+    spatial_hash: &SpatialHash,
+    rng: &mut StdRng,
+    explosive_option: Option<&Explosive>,
+    detonation_event_writer: &mut EventWriter<Detonation>,
+    source_option: Option<&ProjectileSource>
+) {
+    let mut ray_start = origin;
+    let mut velocity = velocity; // Shadow
+    let mut range_left = HITSCAN_RANGE;
+    let mut power = penetration_power;
+    let mut penetrated = Vec::new();
+    let mut inside = Vec::new();
+    let mut ricochets = 0;
+    loop {
+        let direction = velocity.normalize_or_zero();
+        if direction == Vec2::ZERO {
+            break;
+        }
+        let ray_end = ray_start + direction * range_left;
+        let (ray_hit_t, wall_normal) = trace_path(
+            ray_start,
+            ray_end,
+            &mut velocity,
+            &mut power,
+            &mut penetrated,
+            &mut inside,
+            mass,
+            base_damage_per_speed,
+            target_query,
+            tilemap_query,
+            tile_query,
+            tile_materials,
+            spatial_hash,
+            source_option,
+            true
+        );
+        let segment_end = if let Some(t) = ray_hit_t {ray_start.lerp(ray_end, t)} else {ray_end};
+        commands.spawn((
+            Beam {
+                start: ray_start,
+                end: segment_end,
+                time_left: BEAM_DURATION
+            },
+            ShapeBundle {..default()},
+            Stroke::new(colour, 1.0), // Alpha is set by rebuild_beam_shape
+            ProjectileColour {value: colour},
+            DisplayLayer {
+                index: DisplayLayerIndex::Projectiles,
+                flying: false
             }
-            let (intersection_in, _) = intersections_option.unwrap();
-
-            let entry_wound;
-            let collision_t;
-            if collision_detection::circle_point(target_collider.radius, target_position.value, projectile_previous_position.value) {
-                // Hit from inside
-                collision_t = 0.0; // Filled circle
-                entry_wound = projectile_previous_position.value
-            } else if 0.0 <= intersection_in && intersection_in <= 1.0 {
-                // Hit from outside
-                collision_t = intersection_in;
-                entry_wound = projectile_previous_position.value.lerp(projectile_position.value, intersection_in)
+        ));
+
+        if ray_hit_t.is_none() {
+            break;
+        }
+        if let Some(explosive) = explosive_option { // Explosives go off instead of ricocheting
+            detonation_event_writer.send(Detonation {
+                position: segment_end - direction * WALL_GAP,
+                explosive: explosive.clone(),
+                source: source_option.map(|source| source.shooter),
+                weapon: source_option.map(|source| source.weapon),
+                weapon_name: source_option.and_then(|source| source.weapon_name.clone())
+            });
+            break;
+        }
+        let ricochet_velocity_option = if ricochets < MAX_RICOCHETS_PER_TICK {get_ricochet_velocity(velocity, wall_normal, rng)} else {None};
+        if let (Some(ricochet_velocity), Some(normal)) = (ricochet_velocity_option, wall_normal) {
+            range_left -= ray_start.distance(segment_end);
+            ray_start = segment_end + normal * WALL_GAP;
+            velocity = ricochet_velocity;
+            ricochets += 1;
+            continue;
+        }
+        break;
+    }
+}
+
+pub fn detect_hits(
+    mut commands: Commands,
+    mut projectile_query: Query<(
+        Entity,
+        &mut Position,
+        &PreviousPosition,
+        &mut Velocity,
+        &Mass,
+        &BaseDamagePerSpeed,
+        Option<&mut Penetration>,
+        &ProjectileColour,
+        Option<&FlyingRecoveryRate>,
+        Option<&SpawnedMidTick>,
+        Option<&Explosive>,
+        Option<&ProjectileSource>
+    ), (With<GunProjectile>, Without<DestroyedButRender>)>,
+    mut target_query: Query<(Entity, &Position, &PreviousPosition, &Collider, &mut Hits), Without<GunProjectile>>,
+    tilemap_query: Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    spatial_hash: Res<SpatialHash>,
+    fixed_time: Res<FixedTime>,
+    mut rng: ResMut<SimulationRng>,
+    mut detonation_event_writer: EventWriter<Detonation>
+) {
+    // Starts from previous position and goes to current position.
+    // Ricochets continue the path from the wall in the same tick, each bounce being a new projectile entity so that its tracer starts where the last one ended
+    for (
+        projectile_entity,
+        mut projectile_position,
+        projectile_previous_position,
+        mut projectile_velocity,
+        projectile_mass,
+        projectile_base_damage_per_speed,
+        mut penetration_option,
+        projectile_colour,
+        flying_recovery_rate_option,
+        spawned_mid_tick_option,
+        explosive_option,
+        source_option
+    ) in projectile_query.iter_mut() {
+        let mut ray_start = projectile_previous_position.value;
+        let mut ray_end = projectile_position.value;
+        let mut ray_start_time = if let Some(spawned_mid_tick) = spawned_mid_tick_option {spawned_mid_tick.when} else {0.0}; // Fraction of the tick at which the projectile was at ray_start
+        let mut velocity = projectile_velocity.value;
+        let mut power = if let Some(penetration) = &penetration_option {penetration.power} else {0.0};
+        let mut penetrated = if let Some(penetration) = &penetration_option {penetration.penetrated.clone()} else {Vec::new()};
+        let mut inside = if let Some(penetration) = &penetration_option {penetration.inside.clone()} else {Vec::new()};
+        let mut ricochets = 0;
+
+        loop {
+            let (ray_hit_t, wall_normal) = trace_path(
+                ray_start,
+                ray_end,
+                &mut velocity,
+                &mut power,
+                &mut penetrated,
+                &mut inside,
+                projectile_mass.value,
+                projectile_base_damage_per_speed.value,
+                &mut target_query,
+                &tilemap_query,
+                &tile_query,
+                &tile_materials,
+                &spatial_hash,
+                source_option,
+                false
+            );
+            let ricochet_velocity_option = if ricochets < MAX_RICOCHETS_PER_TICK && explosive_option.is_none() {get_ricochet_velocity(velocity, wall_normal, &mut rng.weapons)} else {None};
+
+            // Finish this stretch of the path, with the first being the existing projectile entity
+            let segment_end = if let Some(t) = ray_hit_t {ray_start.lerp(ray_end, t)} else {ray_end};
+            let segment_entity;
+            if ricochets == 0 {
+                projectile_position.value = segment_end;
+                projectile_velocity.value = velocity;
+                if let Some(penetration) = &mut penetration_option {
+                    penetration.power = power;
+                    penetration.penetrated = penetrated.clone();
+                    penetration.inside = inside.clone();
+                }
+                segment_entity = projectile_entity;
             } else {
-                // Not a hit at this time
-                continue;
-            };
+                segment_entity = spawn_projectile(
+                    &mut commands,
+                    segment_end,
+                    ray_start,
+                    velocity,
+                    projectile_mass.value,
+                    projectile_colour.value,
+                    if let Some(flying_recovery_rate) = flying_recovery_rate_option {flying_recovery_rate.value} else {DEFAULT_FLYING_RECOVERY_RATE},
+                    ray_start_time,
+                    projectile_base_damage_per_speed.value,
+                    Penetration {
+                        power: power,
+                        penetrated: penetrated.clone(),
+                        inside: inside.clone()
+                    },
+                    None, // Explosives don't ricochet
+                    None,
+                    source_option.cloned()
+                );
+            }
 
-            if ray_hit_t.is_none() || collision_t < ray_hit_t.unwrap() {
-                hit_entity = Some(target_entity);
-                hit_entry_wound = Some(entry_wound);
-                ray_hit_t = Some(intersection_in);
+            // Destroy the projectile if we hit a wall or an entity
+            if ray_hit_t.is_none() {
                 break;
             }
-        }
-
-        if let Some(hit_entity) = hit_entity {
-            let hit_entry_wound = hit_entry_wound.unwrap();
-            let (_, _, _, mut target_hits) = target_query.get_mut(hit_entity).unwrap();
+            commands.entity(segment_entity).insert(DestroyedButRender);
+            if let Some(explosive) = explosive_option {
+                detonation_event_writer.send(Detonation {
+                    position: segment_end - velocity.normalize_or_zero() * WALL_GAP,
+                    explosive: explosive.clone(),
+                    source: source_option.map(|source| source.shooter),
+                    weapon: source_option.map(|source| source.weapon),
+                    weapon_name: source_option.and_then(|source| source.weapon_name.clone())
+                });
+            }
 
-            target_hits.value.push(Hit {
-                entry_point: hit_entry_wound,
-                force: projectile_velocity.value * projectile_mass.value, // Could take code from circle-circle collision resolution for this in a future project if it's more correct
-                damage: projectile_velocity.value.length() * projectile_base_damage_per_speed.value * PROJECTILE_DAMAGE_MULTIPLIER,
-                apply_force: true,
-                blood_loss: projectile_velocity.value.length() * projectile_mass.value * PROJECTILE_BLOOD_LOSS_MULTIPLIER
-            });
+            // Carry on from the wall for the rest of the tick
+            if let (Some(ricochet_velocity), Some(normal)) = (ricochet_velocity_option, wall_normal) {
+                let hit_time = ray_start_time + ray_hit_t.unwrap() * (1.0 - ray_start_time);
+                ray_start = segment_end + normal * WALL_GAP;
+                ray_end = ray_start + ricochet_velocity * (1.0 - hit_time) * fixed_time.period.as_secs_f32();
+                ray_start_time = hit_time;
+                velocity = ricochet_velocity;
+                ricochets += 1;
+                continue;
+            }
+            break;
         }
+    }
+}
 
-        // Destroy the projectile if we hit a wall or an entity
-        if let Some(ray_hit_t) = ray_hit_t {
-            projectile_position.value = ray_start.lerp(ray_end, ray_hit_t);
-            commands.entity(projectile_entity).insert(DestroyedButRender);
+pub fn tick_beams(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Beam)>,
+    fixed_time: Res<FixedTime>
+) {
+    for (entity, mut beam) in query.iter_mut() {
+        beam.time_left -= fixed_time.period.as_secs_f32();
+        if beam.time_left <= 0.0 {
+            commands.entity(entity).despawn();
         }
     }
 }
 
 pub fn despawn_stationary_projectiles(
     mut commands: Commands,
-    query: Query<(Entity, &Velocity), With<GunProjectile>>
+    query: Query<(Entity, &Velocity), (With<GunProjectile>, Without<Fuse>)> // Fused ones wait to detonate
 ) {
     for (entity, velocity) in query.iter() {
         if velocity.value.length() == 0.0 {
@@ -0,0 +1,65 @@
+use crate::components::*;
+use bevy::prelude::*;
+
+pub fn use_healing_items(
+    mut commands: Commands,
+    mut item_query: Query<(Entity, &mut Healing, Option<&Parent>)>,
+    mut holder_query: Query<(
+        Option<&Will>,
+        Option<&Alive>,
+        Option<&mut Health>,
+        Option<&mut ContainedBlood>,
+        Option<&mut Wounds>,
+        Option<&mut LegInjury>
+    )>,
+    fixed_time: Res<FixedTime>
+) {
+    for (item_entity, mut healing, parent_option) in item_query.iter_mut() {
+        // Only a willed alive holder can use the item, like with reloading guns
+        let mut use_willed = false;
+        let mut held_by_willed_alive = false;
+        if let Some(parent) = parent_option {
+            if let Ok((will_option, alive_option, _, _, _, _)) = holder_query.get(parent.get()) {
+                if let (Some(will), Some(_)) = (will_option, alive_option) {
+                    use_willed = will.use_item;
+                    held_by_willed_alive = true;
+                }
+            }
+        }
+
+        if !held_by_willed_alive {
+            healing.use_timer = None; // Dropping the item abandons its use
+            continue;
+        }
+        if use_willed && healing.use_timer.is_none() {
+            healing.use_timer = Some(healing.use_time);
+        }
+        if let Some(use_timer) = healing.use_timer {
+            let new_use_timer = use_timer - fixed_time.period.as_secs_f32();
+            if new_use_timer > 0.0 {
+                healing.use_timer = Some(new_use_timer);
+                continue;
+            }
+
+            // Done, so apply it to the holder and use it up
+            let (_, _, health_option, contained_blood_option, wounds_option, leg_injury_option) = holder_query.get_mut(parent_option.unwrap().get()).unwrap();
+            if let Some(mut health) = health_option {
+                health.current = (health.current + healing.health).min(health.maximum);
+            }
+            if let Some(mut contained_blood) = contained_blood_option {
+                contained_blood.amount = (contained_blood.amount + healing.blood).min(contained_blood.maximum_amount).max(contained_blood.amount); // Never takes any away
+            }
+            if healing.stops_bleeding {
+                if let Some(mut wounds) = wounds_option {
+                    wounds.value.clear();
+                }
+            }
+            if healing.mends_legs {
+                if let Some(mut leg_injury) = leg_injury_option {
+                    leg_injury.speed_multiplier = 1.0;
+                }
+            }
+            commands.entity(item_entity).despawn_recursive();
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::components::*;
 use crate::events::*;
+use crate::resources::*;
 use bevy::prelude::*;
 
 pub fn send_dropping_events(
@@ -7,16 +8,21 @@ pub fn send_dropping_events(
     query: Query<(&Will, &Children), (With<Holder>, With<Alive>)>
 ) {
     for (will, children) in query.iter() {
-        if !will.drop {
+        if !will.drop && !will.throw {
             continue;
         }
         
         for child_entity in children {
-            dropping_event_writer.send(Dropping {entity: *child_entity});
+            dropping_event_writer.send(Dropping {
+                entity: *child_entity,
+                thrown: will.throw
+            });
         }
     }
 }
 
+const MAX_THROW_SPEED: f32 = 600.0;
+
 pub fn handle_dropping(
     mut commands: Commands,
     mut dropping_events: EventReader<Dropping>,
@@ -26,11 +32,22 @@ pub fn handle_dropping(
     angular_velocity_query: Query<&AngularVelocity>,
     drop_as_grounded_query: Query<&RegroundThreshold, Without<Levitates>>,
     gait_query: Query<&Gait>,
-    child_query: Query<(&HoldingInfo, &Parent)>
+    child_query: Query<(&HoldingInfo, &Parent)>,
+    holder_query: Query<&Holder>,
+    mass_query: Query<&Mass>
 ) {
+    let mut dropped: Vec<Entity> = Vec::new(); // An item can be dropped for more than one reason in a tick, eg its holder dying from an arm hit
     for event in dropping_events.iter() {
         let droppee_entity = event.entity;
-        let (holding_info, parent_component) = child_query.get(droppee_entity).unwrap();
+        if dropped.contains(&droppee_entity) {
+            continue;
+        }
+        dropped.push(droppee_entity);
+        let child_result = child_query.get(droppee_entity);
+        if child_result.is_err() {
+            continue; // Used up earlier in the tick
+        }
+        let (holding_info, parent_component) = child_result.unwrap();
         let held_distance = holding_info.held_distance;
         let held_angle = holding_info.held_angle;
         let parent_entity = parent_component.get();
@@ -49,7 +66,30 @@ pub fn handle_dropping(
             child_commands.insert(Position {value: position.value + Vec2::from_angle(angle).rotate(Vec2::new(held_distance, 0.0))});
         }
         if let Ok(velocity) = velocity_query.get(parent_entity) {
-            child_commands.insert(Velocity {value: velocity.value});
+            let mut droppee_velocity = velocity.value;
+            if event.thrown {
+                // Along the holder's facing, faster for lighter items
+                let angle;
+                if let Ok(angle_component) = angle_query.get(parent_entity) {
+                    angle = angle_component.value;
+                } else {
+                    angle = 0.0;
+                }
+                let throw_strength;
+                if let Ok(holder) = holder_query.get(parent_entity) {
+                    throw_strength = holder.throw_strength;
+                } else {
+                    throw_strength = 0.0;
+                }
+                let throw_speed;
+                if let Ok(mass) = mass_query.get(droppee_entity) {
+                    throw_speed = (throw_strength / mass.value).min(MAX_THROW_SPEED);
+                } else {
+                    throw_speed = MAX_THROW_SPEED;
+                }
+                droppee_velocity += Vec2::from_angle(angle + held_angle) * throw_speed;
+            }
+            child_commands.insert(Velocity {value: droppee_velocity});
 
             let reground_threshold;
             if let Ok(reground_threshold_component) = drop_as_grounded_query.get(droppee_entity) {
@@ -58,7 +98,7 @@ pub fn handle_dropping(
                 reground_threshold = DEFAULT_REGROUND_THRESHOLD;
             }
 
-            if velocity.value.length() <= reground_threshold {
+            if droppee_velocity.length() <= reground_threshold && !event.thrown {
                 child_commands.insert(Grounded {
                     standing: gait_query.contains(droppee_entity),
                     floored_recovery_timer: None
@@ -73,25 +113,81 @@ pub fn handle_dropping(
         if let Ok(angular_velocity) = angular_velocity_query.get(parent_entity) {
             child_commands.insert(AngularVelocity {value: angular_velocity.value});
         }
+        if event.thrown {
+            child_commands.insert(Thrown {thrower: parent_entity});
+        }
     }
 }
 
 pub fn picking_up(
     mut commands: Commands,
     holder_query: Query<(Entity, &Will, Option<&Children>, &Position, &Holder, Option<&Collider>), With<Alive>>,
-    pick_up_able_query: Query<(Entity, &Position), (With<Holdable>, Without<Parent>)>
+    pick_up_able_query: Query<(Entity, &Position), (With<Holdable>, Without<Parent>)>,
+    ammo_pickup_query: Query<(Entity, &Position, &AmmoPickup)>,
+    armour_pickup_query: Query<(Entity, &Position, &ArmourPickup)>,
+    armour_query: Query<&Armour>,
+    mut gun_query: Query<&mut Gun>,
+    spatial_hash: Res<SpatialHash>
 ) {
+    let mut taken_ammo_pickups: Vec<Entity> = Vec::new(); // So that two holders can't take the same pickup on the same tick
+    let mut taken_armour_pickups: Vec<Entity> = Vec::new();
     for (holder_entity, will, children_option, position, holder, collider_option) in holder_query.iter() {
         if !will.pick_up {
             continue;
         }
+
+        // Put on armour in range if not wearing any, which takes the pick up
+        if !armour_query.contains(holder_entity) {
+            let mut put_on = false;
+            for candidate_entity in spatial_hash.query_circle(position.value, holder.pick_up_range) {
+                if taken_armour_pickups.contains(&candidate_entity) {
+                    continue;
+                }
+                if let Ok((armour_pickup_entity, armour_pickup_position, armour_pickup)) = armour_pickup_query.get(candidate_entity) {
+                    if position.value.distance(armour_pickup_position.value) <= holder.pick_up_range {
+                        commands.entity(holder_entity).insert(armour_pickup.armour.clone());
+                        commands.entity(armour_pickup_entity).despawn();
+                        taken_armour_pickups.push(armour_pickup_entity);
+                        put_on = true;
+                        break;
+                    }
+                }
+            }
+            if put_on {
+                continue;
+            }
+        }
         if let Some(children) = children_option {
             if children.len() > 0 {
+                // Already holding something, so take ammo for it instead if it's a gun
+                for child_entity in children.iter() {
+                    if let Ok(mut gun) = gun_query.get_mut(*child_entity) {
+                        for candidate_entity in spatial_hash.query_circle(position.value, holder.pick_up_range) {
+                            if taken_ammo_pickups.contains(&candidate_entity) {
+                                continue;
+                            }
+                            if let Ok((ammo_pickup_entity, ammo_pickup_position, ammo_pickup)) = ammo_pickup_query.get(candidate_entity) {
+                                if position.value.distance(ammo_pickup_position.value) <= holder.pick_up_range {
+                                    gun.reserve_ammo += ammo_pickup.rounds;
+                                    commands.entity(ammo_pickup_entity).despawn();
+                                    taken_ammo_pickups.push(ammo_pickup_entity);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
                 continue;
             }
         }
 
-        for (potential_child_entity, potential_child_position) in pick_up_able_query.iter() {
+        // Holdables are found through the spatial hash, so they need colliders to be picked up
+        for candidate_entity in spatial_hash.query_circle(position.value, holder.pick_up_range) {
+            let pick_up_able_result = pick_up_able_query.get(candidate_entity);
+            if pick_up_able_result.is_err() {
+                continue;
+            }
+            let (potential_child_entity, potential_child_position) = pick_up_able_result.unwrap();
             if position.value.distance(potential_child_position.value) <= holder.pick_up_range {
                 // Shouldn't matter if two entities pick up the same entity on the same tick (TODO: test)
                 commands.entity(holder_entity).push_children(&[potential_child_entity]);
@@ -109,13 +205,13 @@ pub fn picking_up(
                 child_commands.remove::<AngularVelocity>();
                 child_commands.remove::<Grounded>();
                 child_commands.remove::<Flying>();
+                child_commands.remove::<Thrown>(); // Caught
                 break;
             }
         }
     }
 }
 
-#[cfg(debug_assertions)]
 pub fn check_consistent_hierarchy_state(
     child_query: Query<(Entity, &Parent)>,
     holder_query: Query<&Holder>,
@@ -1,5 +1,8 @@
 use crate::components::*;
+use crate::resources::*;
+use crate::util::*;
 use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
 use std::f32::consts::TAU;
 
 fn proper_signum(x: f32) -> f32 {
@@ -27,17 +30,23 @@ pub fn walking(
         &Will,
         Option<&Angle>,
         Option<&Grounded>,
-        Option<&Levitates>
+        Option<&Levitates>,
+        Option<&Position>,
+        Option<&LegInjury>
     ), With<Alive>>,
-    time: Res<Time>
+    main_tilemap_query: Query<(&TilemapSize, &TileStorage), With<MainTilemap>>,
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    fixed_time: Res<FixedTime>
 ) {
-    for (mut velocity, gait, will, angle_option, grounded_option, levitates_option) in query.iter_mut() {
+    let (tilemap_size, tile_storage) = main_tilemap_query.get_single().unwrap();
+    for (mut velocity, gait, will, angle_option, grounded_option, levitates_option, position_option, leg_injury_option) in query.iter_mut() {
         if !(grounded_option.is_some() || levitates_option.is_some()) {
             continue; // Not grounded *or* levitating, can't walk
         }
 
-        let max_speed;
-        let acceleration;
+        let mut max_speed;
+        let mut acceleration;
         if let None = levitates_option {
             // Grounded is definitely some
             let grounded = grounded_option.unwrap();
@@ -48,11 +57,23 @@ pub fn walking(
                 max_speed = gait.floored_max_speed;
                 acceleration = gait.floored_acceleration;
             }
+            // Slippery floors give less grip to push off with. Grippy ones don't help past what the legs can do
+            if let Some(position) = position_option {
+                if let Some(tile_position) = tilemap::get_tile_position(position.value, tilemap_size) {
+                    if let Some(tile_entity) = tile_storage.get(&tile_position) {
+                        acceleration *= tile_materials.get(tile_query.get(tile_entity).unwrap()).floor_friction_multiplier.min(1.0);
+                    }
+                }
+            }
         } else {
             max_speed = gait.standing_max_speed;
             acceleration = gait.standing_acceleration;
         }
 
+        if let Some(leg_injury) = leg_injury_option {
+            max_speed *= leg_injury.speed_multiplier;
+        }
+
         let target_relative_velocity = will.target_relative_velocity_multiplier.unwrap_or(Vec2::ZERO) * max_speed;
         let entity_angle;
         if let Some(angle) = angle_option {
@@ -72,8 +93,8 @@ pub fn walking(
         }
         let acceleration_distribution = direction * acceleration; // So that you don't get to use all of acceleration on both axes
 
-        relative_velocity.x = locomotion_handle_axis(relative_velocity.x, target_relative_velocity.x, acceleration_distribution.x, time.delta_seconds());
-        relative_velocity.y = locomotion_handle_axis(relative_velocity.y, target_relative_velocity.y, acceleration_distribution.y, time.delta_seconds());
+        relative_velocity.x = locomotion_handle_axis(relative_velocity.x, target_relative_velocity.x, acceleration_distribution.x, fixed_time.period.as_secs_f32());
+        relative_velocity.y = locomotion_handle_axis(relative_velocity.y, target_relative_velocity.y, acceleration_distribution.y, fixed_time.period.as_secs_f32());
 
         velocity.value = Vec2::from_angle(rotate_angle).rotate(relative_velocity);
     }
@@ -94,7 +115,7 @@ pub fn turning(
             With<Alive>
         )
     >,
-    time: Res<Time>
+    fixed_time: Res<FixedTime>
 ) {
     for (mut angular_velocity, angular_gait, will) in query.iter_mut() {
         let target_angular_velocity = will.target_angular_velocity_multiplier.unwrap_or(0.0) * angular_gait.max_speed;
@@ -102,7 +123,7 @@ pub fn turning(
             angular_velocity.value,
             target_angular_velocity,
             angular_gait.acceleration * proper_signum(target_angular_velocity - angular_velocity.value),
-            time.delta_seconds()
+            fixed_time.period.as_secs_f32()
         );
     }
 }
@@ -0,0 +1,148 @@
+use crate::components::*;
+use crate::resources::*;
+use crate::util::*;
+use crate::util::collision_detection;
+use crate::systems::startup::{TILEMAP_OFFSET, TILE_SIZE};
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+const MELEE_DAMAGE_PER_FORCE: f32 = 1.0 / 10000.0;
+const MELEE_BLOOD_LOSS_PER_FORCE: f32 = 0.001;
+
+pub fn melee(
+    // Previous positions/angles because that's what the spatial hash was built from this tick, like tick_guns
+    mut holder_query: Query<(
+        Entity,
+        &mut Melee,
+        Option<&Will>,
+        Option<&Alive>,
+        &PreviousPosition,
+        Option<&PreviousAngle>,
+        Option<&Collider>,
+        Option<&Children>
+    )>,
+    item_query: Query<(&Mass, Option<&Collider>, Option<&Weapon>), With<HoldingInfo>>,
+    healing_query: Query<&Healing>,
+    mut target_query: Query<(&PreviousPosition, &Collider, &mut Hits)>,
+    tilemap_query: Query<(&TilemapTileSize, &TileStorage, &TilemapSize), With<WallTilemap>>,
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    spatial_hash: Res<SpatialHash>,
+    fixed_time: Res<FixedTime>
+) {
+    let mut swings: Vec<(Entity, Option<Entity>, Option<String>, Vec2, f32, f32, f32, f32)> = Vec::new(); // Swinger, swung item and its name, position, facing, arc, reach from centre, force
+    for (
+        entity,
+        mut melee,
+        will_option,
+        alive_option,
+        previous_position,
+        previous_angle_option,
+        collider_option,
+        children_option
+    ) in holder_query.iter_mut() {
+        melee.cooldown_timer = (melee.cooldown_timer - fixed_time.period.as_secs_f32()).max(0.0);
+        if melee.cooldown_timer > 0.0 || alive_option.is_none() {
+            continue;
+        }
+        if let Some(will) = will_option {
+            if !will.melee {
+                continue;
+            }
+        } else {
+            continue;
+        }
+        if let Some(children) = children_option {
+            if children.iter().any(|child_entity| healing_query.get(*child_entity).map_or(false, |healing| healing.use_timer.is_some())) {
+                continue; // Hands are busy
+            }
+        }
+        melee.cooldown_timer = melee.cooldown;
+
+        // Swing the held item if there is one, otherwise a fist
+        let mut swung_mass = melee.unarmed_mass;
+        let mut swung_item = None;
+        let mut swung_item_name = None;
+        let mut reach = melee.reach;
+        if let Some(children) = children_option {
+            for child_entity in children.iter() {
+                if let Ok((item_mass, item_collider_option, item_weapon_option)) = item_query.get(*child_entity) {
+                    swung_mass = item_mass.value;
+                    swung_item = Some(*child_entity);
+                    swung_item_name = item_weapon_option.map(|weapon| weapon.name.clone());
+                    if let Some(item_collider) = item_collider_option {
+                        reach += item_collider.radius * 2.0;
+                    }
+                    break;
+                }
+            }
+        }
+        if let Some(collider) = collider_option {
+            reach += collider.radius;
+        }
+        let facing = if let Some(previous_angle) = previous_angle_option {previous_angle.value} else {0.0};
+        swings.push((entity, swung_item, swung_item_name, previous_position.value, facing, melee.arc, reach, swung_mass * melee.swing_speed));
+    }
+
+    let (tile_size, tile_storage, tilemap_size) = tilemap_query.get_single().unwrap();
+    for (swinger_entity, swung_item, swung_item_name, position, facing, arc, reach, force) in swings {
+        for candidate_entity in spatial_hash.query_circle(position, reach) {
+            if candidate_entity == swinger_entity {
+                continue;
+            }
+            let target_result = target_query.get_mut(candidate_entity);
+            if target_result.is_err() {
+                continue;
+            }
+            let (target_previous_position, target_collider, mut target_hits) = target_result.unwrap();
+
+            // In reach of its nearest point and in front, within the arc
+            let offset = target_previous_position.value - position;
+            if offset.length() - target_collider.radius > reach {
+                continue;
+            }
+            let direction = offset.normalize_or_zero();
+            if direction != Vec2::ZERO && direction.angle_between(Vec2::from_angle(facing)).abs() > arc / 2.0 {
+                continue;
+            }
+
+            // Not through walls, though things that let projectiles through (like grating) don't stop a swing either
+            let mut blocked = false;
+            for intersection in collision_detection::new_grid_raycast(
+                position, target_previous_position.value, tile_size.x, tile_size.y, TILEMAP_OFFSET - TILE_SIZE / 2.0
+            ) {
+                if intersection.intersection_t <= 0.0 {
+                    continue; // The tile the swinger is in
+                }
+                let tile_position_option = tilemap::get_tile_position_from_grid(intersection.tile_x, intersection.tile_y, tilemap_size);
+                if tile_position_option.is_none() {
+                    continue;
+                }
+                if let Some(tile_entity) = tile_storage.get(&tile_position_option.unwrap()) {
+                    if tile_materials.get(tile_query.get(tile_entity).unwrap()).projectile_penetrability == 0.0 {
+                        blocked = true;
+                        break;
+                    }
+                }
+            }
+            if blocked {
+                continue;
+            }
+
+            let hit_force = direction * force;
+            target_hits.value.push(Hit {
+                entry_point: target_previous_position.value - direction * target_collider.radius,
+                force: hit_force,
+                damage: force * MELEE_DAMAGE_PER_FORCE,
+                damage_type: DamageType::Blunt,
+                source: Some(swinger_entity),
+                weapon: swung_item,
+                weapon_name: swung_item_name.clone(),
+                apply_force: true,
+                blood_loss: force * MELEE_BLOOD_LOSS_PER_FORCE,
+                bleed_rate: 0.0,
+                exit_wound: false
+            });
+        }
+    }
+}
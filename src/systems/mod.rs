@@ -8,3 +8,7 @@ pub mod physics;
 pub mod graphics;
 pub mod gore;
 pub mod damage;
+pub mod weapon_definitions;
+pub mod explosives;
+pub mod melee;
+pub mod healing;
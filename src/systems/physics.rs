@@ -1,14 +1,46 @@
 use crate::components::*;
+use crate::resources::*;
 use crate::util::*;
 use crate::systems::startup::{TILEMAP_OFFSET, TILE_SIZE};
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 
-pub fn collision(
+const SWEEP_STOP_DISTANCE: f32 = 0.01; // How far short of a wall tile a swept centre stops, so that the per-axis resolution pushes it out of the correct side
+
+fn get_swept_movement_fraction(start: Vec2, movement: Vec2, tilemap_size: &TilemapSize, tile_storage: &TileStorage) -> f32 { // Not a system
+    // Follow the centre's path through the tile grid and stop it before it enters a wall tile, so that nothing can tunnel through walls.
+    // The radius is left to the per-axis resolution, which can only push a circle out of the side its centre is on
+    let movement_length = movement.length();
+    if movement_length == 0.0 {
+        return 1.0;
+    }
+    for intersection in collision_detection::new_grid_raycast(
+        start, start + movement, TILE_SIZE, TILE_SIZE, TILEMAP_OFFSET - TILE_SIZE / 2.0
+    ) {
+        if intersection.intersection_t == 0.0 {
+            continue; // Starting inside a wall tile is left to the per-axis resolution
+        }
+        let tile_position_option = tilemap::get_tile_position_from_grid(intersection.tile_x, intersection.tile_y, tilemap_size);
+        if tile_position_option.is_none() {
+            continue;
+        }
+        if tile_storage.get(&tile_position_option.unwrap()).is_some() {
+            return (intersection.intersection_t - SWEEP_STOP_DISTANCE / movement_length).max(0.0);
+        }
+    }
+    return 1.0;
+}
+
+const WALL_IMPACT_DAMAGE_PER_FORCE: f32 = 1.0 / 200000.0;
+const WALL_IMPACT_BLOOD_LOSS_PER_FORCE: f32 = 0.001;
+
+pub fn wall_collision(
     mut collider_query: Query<(&Collider, &mut Position, &mut Velocity, Option<&Mass>, Option<&Restitution>, Option<&Children>, Option<(&mut Hits, &HitForceThreshold)>)>,
     child_mass_query: Query<&Mass>,
     wall_tilemap_query: Query<(&TilemapSize, &TileStorage), With<WallTilemap>>,
-    time: Res<Time>
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    fixed_time: Res<FixedTime>
 ) {
     // Entity-level collisions
     let (tilemap_size, tile_storage) = wall_tilemap_query.get_single().unwrap();
@@ -16,36 +48,41 @@ pub fn collision(
         entity_collider,
         mut entity_position,
         mut entity_velocity,
-        _, // entity_mass_option,
+        entity_mass_option,
         entity_restitution_option,
-        _, // entity_children_option,
-        _ // mut entity_hit_related_option
+        entity_children_option,
+        entity_hit_related_option
     ) in collider_query.iter_mut() {
         // Non-solid colliders still can't pass through walls, so no guard clause here
 
         // Get mass and restitution
-        // Mass is unused with hits being scrapped to a "TODO"
-        // let mut entity_mass;
-        // if let Some(entity_mass_component) = entity_mass_option {
-        //     entity_mass = entity_mass_component.value;
-        //     if let Some(children) = entity_children_option {
-        //         for child_entity in children.iter() {
-        //             if let Ok(child_mass) = child_mass_query.get(*child_entity) {
-        //                 entity_mass += child_mass.value;
-        //             }
-        //         }
-        //     }
-        // } else {
-        //     entity_mass = 0.0;
-        // }
+        let mut entity_mass;
+        if let Some(entity_mass_component) = entity_mass_option {
+            entity_mass = entity_mass_component.value;
+            if let Some(children) = entity_children_option {
+                for child_entity in children.iter() {
+                    if let Ok(child_mass) = child_mass_query.get(*child_entity) {
+                        entity_mass += child_mass.value;
+                    }
+                }
+            }
+        } else {
+            entity_mass = 0.0;
+        }
         let entity_restitution = if let Some(entity_restitution_component) = entity_restitution_option {
             entity_restitution_component.value
         } else {
             DEFAULT_RESTITUTION
         };
 
+        // The rest of the movement is lost if the sweep stops the entity at a wall
+        let movement = entity_velocity.value * fixed_time.period.as_secs_f32();
+        let movement = movement * get_swept_movement_fraction(entity_position.value, movement, tilemap_size, tile_storage);
+
+        let mut wall_velocity_change = Vec2::ZERO; // Summed over every tile hit this tick, for the impact's Hit
+
         // Do x axis
-        entity_position.value.x += entity_velocity.value.x * time.delta_seconds(); // Apply x velocity
+        entity_position.value.x += movement.x; // Apply x velocity
         // Ignoring the -2 and +2, this "nearby tiles only" calculation probably changes things when the circle's circumference lies exactly on a tile edge
         // Widening the x by 2 tiles on either side should be enough for any issues with tiles moving the entity out of the zone of checked tiles
         let lower_x = (((entity_position.value.x - TILEMAP_OFFSET.x + TILE_SIZE / 2.0 - entity_collider.radius) / TILE_SIZE).floor() - 2.0).max(0.0).min((tilemap_size.x - 1) as f32) as u32;
@@ -55,7 +92,8 @@ pub fn collision(
         for x in lower_x..=upper_x {
             for y in lower_y..=upper_y {
                 // Guard clauses
-                if tile_storage.get(&TilePos {x: x, y: y}).is_none() {
+                let tile_entity_option = tile_storage.get(&TilePos {x: x, y: y});
+                if tile_entity_option.is_none() {
                     continue;
                 }
                 if !collision_detection::circle_aabb(
@@ -83,16 +121,17 @@ pub fn collision(
                     Vec2::new(x as f32, y as f32) * TILE_SIZE - Vec2::splat(TILE_SIZE / 2.0) + TILEMAP_OFFSET,
                     Vec2::ZERO,
                     1.0,
-                    DEFAULT_RESTITUTION // TODO: Tilemap
+                    tile_materials.get(tile_query.get(tile_entity_option.unwrap()).unwrap()).restitution
                 );
 
                 entity_velocity.value.x += entity_velocity_change.x;
+                wall_velocity_change.x += entity_velocity_change.x;
                 entity_position.value.x += entity_position_change.x;
             }
         }
 
         // Do y axis
-        entity_position.value.y += entity_velocity.value.y * time.delta_seconds(); // Apply y velocity
+        entity_position.value.y += movement.y; // Apply y velocity
         let lower_x = (((entity_position.value.x - TILEMAP_OFFSET.x + TILE_SIZE / 2.0 - entity_collider.radius) / TILE_SIZE).floor()      ).max(0.0).min((tilemap_size.x - 1) as f32) as u32;
         let upper_x = (((entity_position.value.x - TILEMAP_OFFSET.x + TILE_SIZE / 2.0 + entity_collider.radius) / TILE_SIZE).floor()      ).max(0.0).min((tilemap_size.x - 1) as f32) as u32;
         let lower_y = (((entity_position.value.y - TILEMAP_OFFSET.y + TILE_SIZE / 2.0 - entity_collider.radius) / TILE_SIZE).floor() - 2.0).max(0.0).min((tilemap_size.y - 1) as f32) as u32;
@@ -100,7 +139,8 @@ pub fn collision(
         for x in lower_x..=upper_x {
             for y in lower_y..=upper_y {
                 // Guard clauses
-                if tile_storage.get(&TilePos {x: x, y: y}).is_none() {
+                let tile_entity_option = tile_storage.get(&TilePos {x: x, y: y});
+                if tile_entity_option.is_none() {
                     continue;
                 }
                 if !collision_detection::circle_aabb(
@@ -128,53 +168,101 @@ pub fn collision(
                     Vec2::new(x as f32, y as f32) * TILE_SIZE - Vec2::splat(TILE_SIZE / 2.0) + TILEMAP_OFFSET,
                     Vec2::ZERO,
                     1.0,
-                    DEFAULT_RESTITUTION // TODO: Tilemap
+                    tile_materials.get(tile_query.get(tile_entity_option.unwrap()).unwrap()).restitution
                 );
 
                 entity_velocity.value.y += entity_velocity_change.y;
+                wall_velocity_change.y += entity_velocity_change.y;
                 entity_position.value.y += entity_position_change.y;
             }
         }
 
-        // Scrapping entity-level hits to a "TODO", if you're moving fast enough for that you're going to clip through the walls
-        // if let Some((ref mut hits, hit_force_threshold)) = entity_hit_related_option {
-        //     let force = entity_velocity_change * entity_mass;
-        //     if force.length() >= hit_force_threshold.value {
-        //         hits.value.push(Hit {
-        //             entry_point: Vec2::ZERO, // TODO
-        //             force: force,
-        //             damage: 0.0, // TODO
-        //             apply_force: false,
-        //             blood_loss: 0.0
-        //         });
-        //     }
-        // }
+        if let Some((mut hits, hit_force_threshold)) = entity_hit_related_option {
+            let force = wall_velocity_change * entity_mass;
+            if force.length() > 0.0 && force.length() >= hit_force_threshold.value {
+                hits.value.push(Hit {
+                    // The wall pushes back along the velocity change, so the impact is on the opposite side of the circle
+                    entry_point: entity_position.value - wall_velocity_change.normalize() * entity_collider.radius,
+                    force: force,
+                    damage: force.length() * WALL_IMPACT_DAMAGE_PER_FORCE,
+                    damage_type: DamageType::Blunt,
+                    source: None, // The body's LastDamage keeps whoever sent it flying
+                    weapon: None,
+                    weapon_name: None,
+                    apply_force: false,
+                    blood_loss: force.length() * WALL_IMPACT_BLOOD_LOSS_PER_FORCE,
+                    bleed_rate: 0.0,
+                    exit_wound: false
+                });
+            }
+        }
     }
+}
 
-    // Entity-entity collisions
-    let mut combinations = collider_query.iter_combinations_mut();
-    while let Some([
-        (
-            a_collider,
-            mut a_position,
-            mut a_velocity,
-            a_mass_option,
-            a_restitution_option,
-            a_children_option,
-            a_hit_related_option
-        ), (
-            b_collider,
-            mut b_position,
-            mut b_velocity,
-            b_mass_option,
-            b_restitution_option,
-            b_children_option,
-            b_hit_related_option
-        )
-    ]) = combinations.fetch_next() {
-        if !(a_collider.solid && b_collider.solid) {
+pub fn rebuild_spatial_hash(
+    query: Query<(Entity, &Position, &Collider)>,
+    mut spatial_hash: ResMut<SpatialHash>
+) {
+    spatial_hash.clear();
+    for (entity, position, collider) in query.iter() {
+        spatial_hash.insert(entity, position.value, collider.radius);
+    }
+}
+
+const THROWN_ITEM_DAMAGE_PER_FORCE: f32 = 1.0 / 20000.0;
+const COLLISION_FRICTION: f32 = 0.3; // Coefficient between colliding bodies' surfaces, limiting the sideways impulse to this times the head-on one
+
+pub fn collision(
+    mut collider_query: Query<(&Collider, &mut Position, &mut Velocity, Option<&Mass>, Option<&Restitution>, Option<&Children>, Option<(&mut Hits, &HitForceThreshold)>, Option<&Thrown>, Option<&mut AngularVelocity>, Option<&Weapon>)>,
+    child_mass_query: Query<&Mass>,
+    spatial_hash: Res<SpatialHash>
+) {
+    // Entity-entity collisions, only between entities that share a spatial hash cell
+    for (a_entity, b_entity) in spatial_hash.get_pairs() {
+        let colliders_result = collider_query.get_many_mut([a_entity, b_entity]);
+        if colliders_result.is_err() {
+            continue; // Not a moving collider
+        }
+        let [
+            (
+                a_collider,
+                mut a_position,
+                mut a_velocity,
+                a_mass_option,
+                a_restitution_option,
+                a_children_option,
+                a_hit_related_option,
+                a_thrown_option,
+                mut a_angular_velocity_option,
+                a_weapon_option
+            ), (
+                b_collider,
+                mut b_position,
+                mut b_velocity,
+                b_mass_option,
+                b_restitution_option,
+                b_children_option,
+                b_hit_related_option,
+                b_thrown_option,
+                mut b_angular_velocity_option,
+                b_weapon_option
+            )
+        ] = colliders_result.unwrap();
+
+        // Thrown items strike whatever they hit except their thrower, even if they aren't normally solid
+        if !((a_collider.solid || a_thrown_option.is_some()) && (b_collider.solid || b_thrown_option.is_some())) {
             continue;
         }
+        if let Some(a_thrown) = a_thrown_option {
+            if a_thrown.thrower == b_entity {
+                continue;
+            }
+        }
+        if let Some(b_thrown) = b_thrown_option {
+            if b_thrown.thrower == a_entity {
+                continue;
+            }
+        }
         if collision_detection::circle_circle(a_collider.radius, a_position.value, b_collider.radius, b_position.value) {
             let mut a_mass;
             if let Some(a_mass_component) = a_mass_option {
@@ -222,34 +310,81 @@ pub fn collision(
                 b_restitution = DEFAULT_RESTITUTION;
             }
 
-            let ((a_acceleration, b_acceleration), (a_shift, b_shift)) = collision_resolution::circle_circle(
+            let ((mut a_acceleration, mut b_acceleration), (a_shift, b_shift)) = collision_resolution::circle_circle(
                 a_position.value, a_collider.radius, a_velocity.value, a_mass, a_restitution,
                 b_position.value, b_collider.radius, b_velocity.value, b_mass, b_restitution
             );
 
+            // The contact point, on each circle's edge towards the other
+            let a_to_b_direction = (b_position.value - a_position.value).normalize_or_zero();
+            let a_contact_point = a_position.value + a_to_b_direction * a_collider.radius;
+            let b_contact_point = b_position.value - a_to_b_direction * b_collider.radius;
+
+            // Friction between the surfaces sliding past each other at the contact point pushes them sideways and turns them
+            let head_on_impulse = a_acceleration.length() * a_mass;
+            if head_on_impulse > 0.0 {
+                let a_offset = a_contact_point - a_position.value;
+                let b_offset = b_contact_point - b_position.value;
+                let a_moment_of_inertia = collision_resolution::get_moment_of_inertia(a_mass, a_collider.radius);
+                let b_moment_of_inertia = collision_resolution::get_moment_of_inertia(b_mass, b_collider.radius);
+                let a_angular_speed = if let Some(a_angular_velocity) = &a_angular_velocity_option {a_angular_velocity.value} else {0.0};
+                let b_angular_speed = if let Some(b_angular_velocity) = &b_angular_velocity_option {b_angular_velocity.value} else {0.0};
+                let tangent = a_to_b_direction.perp();
+                let sliding_speed = (b_velocity.value + b_offset.perp() * b_angular_speed - a_velocity.value - a_offset.perp() * a_angular_speed).dot(tangent); // Of b's surface past a's
+                // How much sliding speed a unit of impulse takes away, counting only the bodies that can turn as turning
+                let mut inverse_effective_mass = 1.0 / a_mass + 1.0 / b_mass;
+                if a_angular_velocity_option.is_some() {
+                    inverse_effective_mass += a_offset.length_squared() / a_moment_of_inertia;
+                }
+                if b_angular_velocity_option.is_some() {
+                    inverse_effective_mass += b_offset.length_squared() / b_moment_of_inertia;
+                }
+                let friction_impulse = tangent * (sliding_speed.abs() / inverse_effective_mass).min(COLLISION_FRICTION * head_on_impulse) * sliding_speed.signum(); // On a, and the opposite on b
+                a_acceleration += friction_impulse / a_mass;
+                b_acceleration -= friction_impulse / b_mass;
+                if let Some(a_angular_velocity) = &mut a_angular_velocity_option {
+                    a_angular_velocity.value += a_offset.perp_dot(friction_impulse) / a_moment_of_inertia;
+                }
+                if let Some(b_angular_velocity) = &mut b_angular_velocity_option {
+                    b_angular_velocity.value += b_offset.perp_dot(-friction_impulse) / b_moment_of_inertia;
+                }
+            }
+
             a_velocity.value += a_acceleration;
             if let Some((mut a_hits, a_hit_force_threshold)) = a_hit_related_option {
                 let a_force = a_acceleration * a_mass;
-                if a_force.length() >= a_hit_force_threshold.value {
+                if a_force.length() >= a_hit_force_threshold.value || b_thrown_option.is_some() {
                     a_hits.value.push(Hit {
-                        entry_point: Vec2::ZERO, // TODO
+                        entry_point: a_contact_point,
                         force: a_force,
-                        damage: 0.0, // TODO
+                        damage: if b_thrown_option.is_some() {a_force.length() * THROWN_ITEM_DAMAGE_PER_FORCE} else {0.0}, // TODO for everything else
+                        damage_type: DamageType::Blunt,
+                        source: if let Some(b_thrown) = b_thrown_option {Some(b_thrown.thrower)} else {Some(b_entity)},
+                        weapon: if b_thrown_option.is_some() {Some(b_entity)} else {None},
+                        weapon_name: if b_thrown_option.is_some() {b_weapon_option.map(|weapon| weapon.name.clone())} else {None},
                         apply_force: false,
-                        blood_loss: 0.0
+                        blood_loss: 0.0,
+                        bleed_rate: 0.0,
+                        exit_wound: false
                     });
                 }
             }
             b_velocity.value += b_acceleration;
             if let Some((mut b_hits, b_hit_force_threshold)) = b_hit_related_option {
                 let b_force = b_acceleration * b_mass;
-                if b_force.length() >= b_hit_force_threshold.value {
+                if b_force.length() >= b_hit_force_threshold.value || a_thrown_option.is_some() {
                     b_hits.value.push(Hit {
-                        entry_point: Vec2::ZERO, // TODO
+                        entry_point: b_contact_point,
                         force: b_force,
-                        damage: 0.0, // TODO
+                        damage: if a_thrown_option.is_some() {b_force.length() * THROWN_ITEM_DAMAGE_PER_FORCE} else {0.0}, // TODO for everything else
+                        damage_type: DamageType::Blunt,
+                        source: if let Some(a_thrown) = a_thrown_option {Some(a_thrown.thrower)} else {Some(a_entity)},
+                        weapon: if a_thrown_option.is_some() {Some(a_entity)} else {None},
+                        weapon_name: if a_thrown_option.is_some() {a_weapon_option.map(|weapon| weapon.name.clone())} else {None},
                         apply_force: false,
-                        blood_loss: 0.0
+                        blood_loss: 0.0,
+                        bleed_rate: 0.0,
+                        exit_wound: false
                     });
                 }
             }
@@ -262,19 +397,19 @@ pub fn collision(
 
 pub fn apply_velocity(
     mut query: Query<(&mut Position, &Velocity), Without<Collider>>, // Without collider because velocity is done in collision for colliders
-    time: Res<Time>
+    fixed_time: Res<FixedTime>
 ) {
     for (mut position, velocity) in query.iter_mut() {
-        position.value += velocity.value * time.delta_seconds();
+        position.value += velocity.value * fixed_time.period.as_secs_f32();
     }
 }
 
 pub fn apply_angular_velocity(
     mut query: Query<(&mut Angle, &AngularVelocity)>,
-    time: Res<Time>
+    fixed_time: Res<FixedTime>
 ) {
     for (mut angle, angular_velocity) in query.iter_mut() {
-        angle.value += angular_velocity.value * time.delta_seconds();
+        angle.value += angular_velocity.value * fixed_time.period.as_secs_f32();
     }
 }
 
@@ -288,7 +423,6 @@ pub fn _monitor_conservation(query: Query<(&Velocity, &Mass)>) {
     println!("Energy: {}, Momentum: {}", energy, momentum);
 }
 
-#[cfg(debug_assertions)]
 pub fn check_consistent_grounded_flying_state(
     query: Query<(
         Option<&Grounded>,
@@ -315,7 +449,7 @@ pub fn manage_flyers(
         Option<&Gait>,
         Option<&RegroundThreshold>
     ), With<Flying>>,
-    time: Res<Time>
+    fixed_time: Res<FixedTime>
 ) {
     for (
         entity,
@@ -332,7 +466,7 @@ pub fn manage_flyers(
         } else {
             speed_reduction = DEFAULT_FLYING_RECOVERY_RATE;
         }
-        let new_speed = (old_speed - speed_reduction * time.delta_seconds()).max(0.0);
+        let new_speed = (old_speed - speed_reduction * fixed_time.period.as_secs_f32()).max(0.0);
 
         if old_speed > 0.0 && new_speed != old_speed {
             velocity.value = velocity.value.normalize() * new_speed;
@@ -354,6 +488,7 @@ pub fn manage_flyers(
                     floored_recovery_time = None;
                 }
                 commands.entity(entity).remove::<Flying>();
+                commands.entity(entity).remove::<Thrown>(); // Landed
                 commands.entity(entity).insert(Grounded {
                     standing: false, // If ordering is as intended, a floored recovery time(r) of 0 should cause the entity to stand immediately
                     floored_recovery_timer: floored_recovery_time
@@ -365,12 +500,12 @@ pub fn manage_flyers(
 
 pub fn manage_flooreds(
     mut query: Query<(&mut Grounded, Option<&Alive>)>,
-    time: Res<Time>
+    fixed_time: Res<FixedTime>
 ) {
     for (mut grounded, alive_option) in query.iter_mut() {
         if let Some(old_timer_state) = grounded.floored_recovery_timer {
             if alive_option.is_some() {
-                let new_timer_state = (old_timer_state - time.delta_seconds()).max(0.0);
+                let new_timer_state = (old_timer_state - fixed_time.period.as_secs_f32()).max(0.0);
                 if new_timer_state > 0.0 {
                     grounded.floored_recovery_timer = Some(new_timer_state);
                 } else {
@@ -388,7 +523,7 @@ pub fn manage_flooreds(
 
 pub fn angular_friction(
     mut query: Query<(&mut AngularVelocity, Option<&FlooredAngularFriction>, Option<&UnflooredAngularFriction>, Option<&Grounded>)>,
-    time: Res<Time>
+    fixed_time: Res<FixedTime>
 ) {
     for (mut angular_velocity, floored_angular_friction_option, unfloored_angular_friction_option, grounded_option) in query.iter_mut() {
         let floored;
@@ -413,24 +548,33 @@ pub fn angular_friction(
             }
         }
 
-        angular_velocity.value = angular_velocity.value.signum() * (angular_velocity.value.abs() - friction * time.delta_seconds()).max(0.0); // Doesn't need to be proper_signum
+        angular_velocity.value = angular_velocity.value.signum() * (angular_velocity.value.abs() - friction * fixed_time.period.as_secs_f32()).max(0.0); // Doesn't need to be proper_signum
     }
 }
 
 pub fn floor_friction(
-    mut query: Query<(&Grounded, Option<&FloorFriction>, &mut Velocity)>,
-    time: Res<Time>
+    mut query: Query<(&Grounded, Option<&FloorFriction>, &mut Velocity, &Position)>,
+    main_tilemap_query: Query<(&TilemapSize, &TileStorage), With<MainTilemap>>,
+    tile_query: Query<&TileTextureIndex>,
+    tile_materials: Res<TileMaterials>,
+    fixed_time: Res<FixedTime>
 ) {
-    for (grounded, floor_friction_option, mut velocity) in query.iter_mut() {
+    let (tilemap_size, tile_storage) = main_tilemap_query.get_single().unwrap();
+    for (grounded, floor_friction_option, mut velocity, position) in query.iter_mut() {
         if !grounded.standing {
-            let friction;
+            let mut friction;
             if let Some(floor_friction) = floor_friction_option {
                 friction = floor_friction.value;
             } else {
                 friction = DEFAULT_FLOOR_FRICTION;
             }
+            if let Some(tile_position) = tilemap::get_tile_position(position.value, tilemap_size) {
+                if let Some(tile_entity) = tile_storage.get(&tile_position) {
+                    friction *= tile_materials.get(tile_query.get(tile_entity).unwrap()).floor_friction_multiplier;
+                }
+            }
             let old_speed = velocity.value.length();
-            let new_speed = (old_speed - friction * time.delta_seconds()).max(0.0);
+            let new_speed = (old_speed - friction * fixed_time.period.as_secs_f32()).max(0.0);
             if old_speed > 0.0 && new_speed != old_speed {
                 velocity.value = velocity.value.normalize() * new_speed;
             }
@@ -1,4 +1,5 @@
 use crate::components::*;
+use crate::resources::*;
 use bevy::prelude::*;
 
 pub fn store_previous_position(mut query: Query<(&mut PreviousPosition, &Position)>) {
@@ -52,3 +53,14 @@ pub fn remove_hits(mut query: Query<&mut Hits>) {
         hits.value.clear();
     }
 }
+
+pub fn assign_simulation_ids(
+    mut commands: Commands,
+    query: Query<Entity, (With<Will>, Without<SimulationId>)>,
+    mut next_simulation_id: ResMut<NextSimulationId>
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(SimulationId {value: next_simulation_id.value});
+        next_simulation_id.value += 1;
+    }
+}
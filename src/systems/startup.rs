@@ -1,5 +1,6 @@
 use crate::components::*;
 use crate::util::*;
+use crate::resources::*;
 use std::f32::consts::TAU;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
@@ -18,12 +19,61 @@ pub fn spawn_camera (mut commands: Commands) {
     );
 }
 
+pub fn starting_scene_is_default(starting_scene: Res<StartingScene>) -> bool { // Run condition
+    return *starting_scene == StartingScene::Default;
+}
+
 pub fn spawn_player(
     mut commands: Commands
 ) {
-    let position = Vec2::ZERO;
-    let angle = 0.0;
-    commands.spawn((
+    let player = spawn_actor(&mut commands, Vec2::ZERO, 0.0, 40000.0);
+    commands.entity(player).insert(Player);
+}
+
+pub fn spawn_other(
+    mut commands: Commands,
+    weapon_definitions: Res<WeaponDefinitions>
+) {
+    spawn_weapon(&mut commands, &weapon_definitions, "shotgun", Vec2::new(100.0, 0.0));
+    spawn_weapon(&mut commands, &weapon_definitions, "machine_gun", Vec2::new(100.0, 100.0));
+    spawn_weapon(&mut commands, &weapon_definitions, "ship_cannon", Vec2::new(100.0, 200.0));
+    spawn_weapon(&mut commands, &weapon_definitions, "railgun", Vec2::new(100.0, 150.0));
+    spawn_weapon(&mut commands, &weapon_definitions, "grenade_launcher", Vec2::new(140.0, 150.0));
+    spawn_grenade(&mut commands, Vec2::new(140.0, 100.0));
+    spawn_bat(&mut commands, Vec2::new(140.0, 60.0));
+    spawn_medkit(&mut commands, Vec2::new(170.0, 60.0));
+    spawn_bandage(&mut commands, Vec2::new(170.0, 80.0));
+    spawn_armour_pickup(&mut commands, Vec2::new(170.0, 100.0));
+    spawn_ammo_pickup(&mut commands, Vec2::new(120.0, 0.0), 12);
+    spawn_ammo_pickup(&mut commands, Vec2::new(120.0, 100.0), 60);
+
+    // Giant mass to gib with
+    // commands.spawn((
+    //     Position {value: Vec2::new(-100.0, 10000.0)},
+    //     Velocity {value: Vec2::new(0.0, -4000.0)},
+    //     Collider {
+    //         radius: 100.0,
+    //         solid: true
+    //     },
+    //     Mass {value: 1000.0},
+    //     Restitution {value: 1.0},
+    //     ShapeBundle {
+    //         // Path is created by rebuild_collider_shape before rendering
+    //         ..default()
+    //     },
+    //     Fill::color(Color::WHITE),
+    //     Stroke::new(Color::WHITE, 1.0),
+    //     DisplayLayer {
+    //         index: DisplayLayerIndex::Actors,
+    //         flying: false
+    //     }
+    // ));
+
+    spawn_actor(&mut commands, Vec2::new(-100.0, 100.0), 0.0, 4000.0);
+}
+
+pub fn spawn_actor(commands: &mut Commands, position: Vec2, angle: f32, hit_force_threshold: f32) -> Entity { // Not a system
+    return commands.spawn((
         ( // Nested to get around bundle size limit
             Position {value: position},
             PreviousPosition {value: position},
@@ -35,6 +85,10 @@ pub fn spawn_player(
                 floored_acceleration: 400.0,
                 floored_recovery_time: 2.0
             },
+            LegInjury {
+                speed_multiplier: 1.0,
+                minimum_speed_multiplier: 0.4
+            },
             FlyingRecoveryRate {value: 800.0},
             RegroundThreshold {value: 210.0},
             TripThreshold {value: 220.0}
@@ -69,19 +123,26 @@ pub fn spawn_player(
                 flying: false
             }
         ),
-        Player,
         (
             Alive,
             Will {..default()},
-            Grounded {
-                standing: true,
-                floored_recovery_timer: None
-            },
             Health {
                 maximum: 1.0,
                 current: 1.0
-            }
+            },
+            BodyRegions {value: vec![
+                // Facing is the front, so the head is only exposed from there. Legs trail behind, arms are at the sides
+                BodyRegion {part: BodyPart::Head, centre_angle: 0.0, half_width: TAU / 16.0, damage_multiplier: 3.0, blood_loss_multiplier: 1.5, gait_multiplier: 1.0, drops_held_items: false},
+                BodyRegion {part: BodyPart::Arm, centre_angle: TAU / 4.0, half_width: TAU / 12.0, damage_multiplier: 0.5, blood_loss_multiplier: 0.75, gait_multiplier: 1.0, drops_held_items: true},
+                BodyRegion {part: BodyPart::Arm, centre_angle: -TAU / 4.0, half_width: TAU / 12.0, damage_multiplier: 0.5, blood_loss_multiplier: 0.75, gait_multiplier: 1.0, drops_held_items: true},
+                BodyRegion {part: BodyPart::Leg, centre_angle: TAU / 2.0, half_width: TAU / 12.0, damage_multiplier: 0.6, blood_loss_multiplier: 1.25, gait_multiplier: 0.7, drops_held_items: false},
+                BodyRegion {part: BodyPart::Torso, centre_angle: 0.0, half_width: TAU / 2.0, damage_multiplier: 1.0, blood_loss_multiplier: 1.0, gait_multiplier: 1.0, drops_held_items: false}
+            ]}
         ),
+        Grounded {
+            standing: true,
+            floored_recovery_timer: None
+        },
         (
             ContainedBlood {
                 drip_time: 0.1,
@@ -93,24 +154,45 @@ pub fn spawn_player(
 
                 leak_rate: 0.0,
                 amount: 1000.0,
+                maximum_amount: 1000.0,
                 drip_timer: 0.5,
                 amount_to_drip: 0.0
             },
+            Wounds {
+                value: Vec::new(),
+                clotting_rate: Some(1.0)
+            },
+            LastDamage {
+                source: None,
+                weapon: None,
+                weapon_name: None,
+                damage_type: None,
+                time_since: 0.0
+            },
             Hits {value: Vec::<Hit>::new()},
             Gibbable,
             GibForceThreshold {value: 400000.0},
-            HitForceThreshold {value: 40000.0}
+            HitForceThreshold {value: hit_force_threshold}
         ),
-        Holder {pick_up_range: 20.0}
-    ));
+        Holder {
+            pick_up_range: 20.0,
+            throw_strength: 6000.0
+        },
+        Melee {
+            reach: 6.0,
+            arc: TAU / 4.0,
+            swing_speed: 300.0,
+            unarmed_mass: 5.0,
+            cooldown: 0.5,
+
+            cooldown_timer: 0.0
+        }
+    )).id();
 }
 
-pub fn spawn_other(
-    mut commands: Commands
-) {
-    // Shotgun
-    let position = Vec2::new(100.0, 0.0);
-    commands.spawn((
+pub fn spawn_weapon(commands: &mut Commands, weapon_definitions: &WeaponDefinitions, name: &str, position: Vec2) -> Entity { // Not a system
+    let definition = weapon_definitions.get(name);
+    return commands.spawn((
         (
             Position {value: position},
             PreviousPosition {value: position},
@@ -118,19 +200,19 @@ pub fn spawn_other(
         ),
         (
             Collider {
-                radius: 5.0,
+                radius: definition.collider_radius,
                 solid: false
             },
-            Mass {value: 10.0},
-            Restitution {value: 0.4},
-            FloorFriction {value: 200.0}
+            Mass {value: definition.mass},
+            Restitution {value: definition.restitution},
+            FloorFriction {value: definition.floor_friction}
         ),
         (
             ShapeBundle {
                 ..default()
             },
-            Fill::color(Color::GRAY),
-            Stroke::new(Color::GRAY, 1.0),
+            Fill::color(definition.colour),
+            Stroke::new(definition.colour, 1.0),
             DisplayLayer {
                 index: DisplayLayerIndex::Items,
                 flying: false
@@ -140,28 +222,14 @@ pub fn spawn_other(
             standing: false,
             floored_recovery_timer: None
         },
-        Gun {
-            projectile_speed: 6000.0,
-            projectile_flying_recovery_rate: 500.0,
-            projectile_spread: Vec2::new(0.05, 0.05),
-            projectile_count: 10,
-            projectile_colour: Color::YELLOW,
-            projectile_mass: 0.01,
-            projectile_base_damage_per_unit: 1.0 / 600.0 / (10.0 - 3.0),
-            muzzle_distance: 5.0,
-            cooldown: 1.0,
-            auto: false,
-
-            cooldown_timer: 0.0,
-            trigger_depressed: false,
-            trigger_depressed_previous_frame: false
-        },
+        definition.new_gun(),
+        Weapon {name: name.to_string()},
         Holdable
-    ));
+    )).id();
+}
 
-    // Machine gun
-    let position = Vec2::new(100.0, 100.0);
-    commands.spawn((
+pub fn spawn_ammo_pickup(commands: &mut Commands, position: Vec2, rounds: u32) -> Entity { // Not a system
+    return commands.spawn((
         (
             Position {value: position},
             PreviousPosition {value: position},
@@ -169,10 +237,10 @@ pub fn spawn_other(
         ),
         (
             Collider {
-                radius: 7.0,
+                radius: 3.0,
                 solid: false
             },
-            Mass {value: 20.0},
+            Mass {value: 2.0},
             Restitution {value: 0.3},
             FloorFriction {value: 300.0}
         ),
@@ -180,8 +248,8 @@ pub fn spawn_other(
             ShapeBundle {
                 ..default()
             },
-            Fill::color(Color::GRAY),
-            Stroke::new(Color::GRAY, 1.0),
+            Fill::color(Color::OLIVE),
+            Stroke::new(Color::OLIVE, 1.0),
             DisplayLayer {
                 index: DisplayLayerIndex::Items,
                 flying: false
@@ -191,28 +259,12 @@ pub fn spawn_other(
             standing: false,
             floored_recovery_timer: None
         },
-        Gun {
-            projectile_speed: 8000.0,
-            projectile_flying_recovery_rate: 250.0,
-            projectile_spread: Vec2::new(0.005, 0.005),
-            projectile_count: 1,
-            projectile_colour: Color::YELLOW,
-            projectile_mass: 0.025,
-            projectile_base_damage_per_unit: 1.0 / 1000.0,
-            muzzle_distance: 7.0,
-            cooldown: 0.1,
-            auto: true,
-    
-            cooldown_timer: 0.0,
-            trigger_depressed: false,
-            trigger_depressed_previous_frame: false
-        },
-        Holdable
-    ));
+        AmmoPickup {rounds: rounds}
+    )).id();
+}
 
-    // Ship cannon
-    let position = Vec2::new(100.0, 200.0);
-    commands.spawn((
+pub fn spawn_armour_pickup(commands: &mut Commands, position: Vec2) -> Entity { // Not a system
+    return commands.spawn((
         (
             Position {value: position},
             PreviousPosition {value: position},
@@ -220,19 +272,19 @@ pub fn spawn_other(
         ),
         (
             Collider {
-                radius: 15.0,
+                radius: 6.0,
                 solid: false
             },
-            Mass {value: 100.0},
-            Restitution {value: 0.3},
+            Mass {value: 10.0},
+            Restitution {value: 0.1},
             FloorFriction {value: 400.0}
         ),
         (
             ShapeBundle {
                 ..default()
             },
-            Fill::color(Color::GRAY),
-            Stroke::new(Color::GRAY, 1.0),
+            Fill::color(Color::SILVER),
+            Stroke::new(Color::SILVER, 1.0),
             DisplayLayer {
                 index: DisplayLayerIndex::Items,
                 flying: false
@@ -242,141 +294,196 @@ pub fn spawn_other(
             standing: false,
             floored_recovery_timer: None
         },
-        Gun {
-            projectile_speed: 20000.0,
-            projectile_flying_recovery_rate: 250.0,
-            projectile_spread: Vec2::new(0.0, 0.0),
-            projectile_count: 1,
-            projectile_colour: Color::YELLOW,
-            projectile_mass: 30.0,
-            projectile_base_damage_per_unit: 1.0 / 100.0,
-            muzzle_distance: 15.0,
-            cooldown: 2.0,
-            auto: false,
-    
-            cooldown_timer: 0.0,
-            trigger_depressed: false,
-            trigger_depressed_previous_frame: false
-        },
-        Holdable
-    ));
-
-    // Giant mass to gib with
-    // commands.spawn((
-    //     Position {value: Vec2::new(-100.0, 10000.0)},
-    //     Velocity {value: Vec2::new(0.0, -4000.0)},
-    //     Collider {
-    //         radius: 100.0,
-    //         solid: true
-    //     },
-    //     Mass {value: 1000.0},
-    //     Restitution {value: 1.0},
-    //     ShapeBundle {
-    //         // Path is created by rebuild_collider_shape before rendering
-    //         ..default()
-    //     },
-    //     Fill::color(Color::WHITE),
-    //     Stroke::new(Color::WHITE, 1.0),
-    //     DisplayLayer {
-    //         index: DisplayLayerIndex::Actors,
-    //         flying: false
-    //     }
-    // ));
+        ArmourPickup {armour: Armour {
+            ballistic_absorption: 0.95,
+            blunt_absorption: 0.3,
+            explosive_absorption: 0.5,
+            blood_loss_multiplier: 0.25,
+            durability: 20.0,
+            shatter_threshold: 15.0
+        }}
+    )).id();
+}
 
-    let position = Vec2::new(-100.0, 100.0);
-    let angle = 0.0;
-    commands.spawn((
-        ( // Nested to get around bundle size limit
+pub fn spawn_grenade(commands: &mut Commands, position: Vec2) -> Entity { // Not a system
+    return commands.spawn((
+        (
             Position {value: position},
             PreviousPosition {value: position},
-            Velocity {value: Vec2::ZERO},
-            Gait {
-                standing_max_speed: 200.0,
-                standing_acceleration: 800.0,
-                floored_max_speed: 100.0,
-                floored_acceleration: 400.0,
-                floored_recovery_time: 2.0
+            Velocity {value: Vec2::ZERO}
+        ),
+        (
+            Collider {
+                radius: 3.0,
+                solid: false
             },
-            FlyingRecoveryRate {value: 800.0},
-            RegroundThreshold {value: 210.0},
-            TripThreshold {value: 220.0}
+            Mass {value: 1.0},
+            Restitution {value: 0.5},
+            FloorFriction {value: 200.0}
         ),
         (
-            Angle {value: angle},
-            PreviousAngle {value: angle},
-            AngularVelocity {value: 0.0},
-            AngularGait {
-                max_speed: TAU / 2.0,
-                acceleration: TAU * 8.0
+            ShapeBundle {
+                ..default()
             },
+            Fill::color(Color::DARK_GREEN),
+            Stroke::new(Color::DARK_GREEN, 1.0),
+            DisplayLayer {
+                index: DisplayLayerIndex::Items,
+                flying: false
+            }
+        ),
+        Grounded {
+            standing: false,
+            floored_recovery_timer: None
+        },
+        Explosive {
+            force: 600000.0,
+            damage: 2.0,
+            radius: 48.0
+        },
+        Fuse { // Lit by pulling the trigger while holding it
+            time: 3.0,
+            time_left: None,
+            lit_by: None
+        },
+        Holdable,
+        Weapon {name: "grenade".to_string()}
+    )).id();
+}
+
+pub fn spawn_bat(commands: &mut Commands, position: Vec2) -> Entity { // Not a system
+    return commands.spawn((
+        (
+            Position {value: position},
+            PreviousPosition {value: position},
+            Velocity {value: Vec2::ZERO}
         ),
         (
             Collider {
-                radius: 10.0,
-                solid: true
+                radius: 6.0,
+                solid: false
             },
-            Mass {value: 100.0},
-            Restitution {value: 0.2},
-            FloorFriction {value: 300.0}
+            Mass {value: 8.0},
+            Restitution {value: 0.4},
+            FloorFriction {value: 250.0}
         ),
         (
             ShapeBundle {
-                // Path is created by rebuild_collider_shape before rendering
                 ..default()
             },
-            Fill::color(Color::WHITE),
-            Stroke::new(Color::WHITE, 1.0),
+            Fill::color(Color::BEIGE),
+            Stroke::new(Color::BEIGE, 1.0),
             DisplayLayer {
-                index: DisplayLayerIndex::Actors,
+                index: DisplayLayerIndex::Items,
                 flying: false
             }
         ),
+        Grounded {
+            standing: false,
+            floored_recovery_timer: None
+        },
+        Holdable,
+        Weapon {name: "bat".to_string()}
+    )).id();
+}
+
+pub fn spawn_medkit(commands: &mut Commands, position: Vec2) -> Entity { // Not a system
+    return commands.spawn((
+        (
+            Position {value: position},
+            PreviousPosition {value: position},
+            Velocity {value: Vec2::ZERO}
+        ),
         (
-            Alive,
-            Will {..default()},
-            Health {
-                maximum: 1.0,
-                current: 1.0
+            Collider {
+                radius: 5.0,
+                solid: false
+            },
+            Mass {value: 3.0},
+            Restitution {value: 0.2},
+            FloorFriction {value: 400.0}
+        ),
+        (
+            ShapeBundle {
+                ..default()
+            },
+            Fill::color(Color::GREEN),
+            Stroke::new(Color::GREEN, 1.0),
+            DisplayLayer {
+                index: DisplayLayerIndex::Items,
+                flying: false
             }
         ),
         Grounded {
-            standing: true,
+            standing: false,
             floored_recovery_timer: None
         },
-        (
-            ContainedBlood {
-                drip_time: 0.1,
-                drip_time_minimum_multiplier: 0.75,
-                smear_drip_time_multiplier: 0.3,
-                colour: Color::RED,
-                minimum_amount: 100.0,
-                death_threshold: Some(500.0),
+        Holdable,
+        Healing {
+            health: 1.0,
+            blood: 300.0,
+            stops_bleeding: true,
+            mends_legs: true,
+            use_time: 3.0,
+            use_timer: None
+        }
+    )).id();
+}
 
-                leak_rate: 0.0,
-                amount: 1000.0,
-                drip_timer: 0.5,
-                amount_to_drip: 0.0
+pub fn spawn_bandage(commands: &mut Commands, position: Vec2) -> Entity { // Not a system
+    return commands.spawn((
+        (
+            Position {value: position},
+            PreviousPosition {value: position},
+            Velocity {value: Vec2::ZERO}
+        ),
+        (
+            Collider {
+                radius: 3.0,
+                solid: false
             },
-            Hits {value: Vec::<Hit>::new()},
-            Gibbable,
-            GibForceThreshold {value: 400000.0},
-            HitForceThreshold {value: 4000.0}
+            Mass {value: 0.5},
+            Restitution {value: 0.1},
+            FloorFriction {value: 400.0}
+        ),
+        (
+            ShapeBundle {
+                ..default()
+            },
+            Fill::color(Color::ANTIQUE_WHITE),
+            Stroke::new(Color::ANTIQUE_WHITE, 1.0),
+            DisplayLayer {
+                index: DisplayLayerIndex::Items,
+                flying: false
+            }
         ),
-        Holder {pick_up_range: 20.0}
-    ));
+        Grounded {
+            standing: false,
+            floored_recovery_timer: None
+        },
+        Holdable,
+        Healing {
+            health: 0.0,
+            blood: 0.0,
+            stops_bleeding: true,
+            mends_legs: false,
+            use_time: 1.0,
+            use_timer: None
+        }
+    )).id();
 }
 
 pub fn spawn_dots(
-    mut commands: Commands
+    mut commands: Commands,
+    mut rng: ResMut<SimulationRng>
 ) {
     let shape = shapes::Circle {
         radius: 2.0,
         ..default()
     };
-    let mut rng = rand::thread_rng();
     for _ in 0..0 {
         commands.spawn((
-            Position {value: random_in_shape::circle(&mut rng, 1000.0)},
+            Position {value: random_in_shape::circle(&mut rng.world_generation, 1000.0)},
             ShapeBundle {
                 path: GeometryBuilder::build_as(&shape),
                 ..default()
@@ -392,10 +499,9 @@ pub const TILE_SIZE: f32 = 8.0;
 
 pub fn spawn_tilemaps(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    array_texture_loader: Res<ArrayTextureLoader>
+    starting_scene: Res<StartingScene>
 ) {
-    let texture_handle: Handle<Image> = asset_server.load("tiles.png");
+    // The texture is added by load_tilemap_texture when rendering
     let map_size = TilemapSize {x: 20, y: 20};
 
     let main_tilemap_entity = commands.spawn((
@@ -426,12 +532,25 @@ pub fn spawn_tilemaps(
                 x == 0 || x == map_size.x - 1 ||
                 y == 0 || y == map_size.y - 1
             {2} else {1};
-            let wall = tile == 2;
+            let mut wall = tile == 2;
+
+            // Show off the tile materials in the default scene, see TileMaterials
+            let mut material_tile_option = None;
+            if *starting_scene == StartingScene::Default {
+                if x == 0 && y >= 5 && y <= 14 {
+                    material_tile_option = Some(3); // Rubber wall
+                } else if x == 6 && y >= 5 && y <= 9 {
+                    material_tile_option = Some(5); // Metal grating, over the floor so that it shows through the gaps
+                    wall = true;
+                } else if x >= 2 && x <= 6 && y >= 15 && y <= 18 {
+                    material_tile_option = Some(4); // Ice floor
+                }
+            }
 
             let main_tile_entity = commands.spawn(TileBundle {
                 position: tile_position,
                 tilemap_id: TilemapId(main_tilemap_entity),
-                texture_index: TileTextureIndex(tile),
+                texture_index: TileTextureIndex(if !wall && material_tile_option.is_some() {material_tile_option.unwrap()} else {tile}),
                 ..Default::default()
             }).id();
             main_tile_storage.set(&tile_position, main_tile_entity);
@@ -440,7 +559,7 @@ pub fn spawn_tilemaps(
             let wall_tile_entity = commands.spawn(TileBundle {
                 position: tile_position,
                 tilemap_id: TilemapId(wall_tilemap_entity),
-                    texture_index: TileTextureIndex(tile),
+                    texture_index: TileTextureIndex(material_tile_option.unwrap_or(tile)),
                 ..Default::default()
             }).id();
             wall_tile_storage.set(&tile_position, wall_tile_entity);
@@ -457,7 +576,6 @@ pub fn spawn_tilemaps(
         map_type,
         size: map_size,
         storage: main_tile_storage,
-        texture: TilemapTexture::Single(texture_handle.clone()),
         tile_size,
         transform: Transform {
             translation: Vec3::new(TILEMAP_OFFSET.x, TILEMAP_OFFSET.y, 0.0),
@@ -471,7 +589,6 @@ pub fn spawn_tilemaps(
         map_type,
         size: map_size,
         storage: wall_tile_storage,
-        texture: TilemapTexture::Single(texture_handle),
         tile_size,
         transform: Transform {
             translation: Vec3::new(TILEMAP_OFFSET.x, TILEMAP_OFFSET.y, 0.0),
@@ -479,10 +596,21 @@ pub fn spawn_tilemaps(
         },
         ..Default::default()
     });
+}
+
+pub fn load_tilemap_texture(
+    mut query: Query<&mut TilemapTexture, Or<(With<MainTilemap>, With<WallTilemap>)>>,
+    asset_server: Res<AssetServer>,
+    array_texture_loader: Res<ArrayTextureLoader>
+) {
+    let texture_handle: Handle<Image> = asset_server.load("tiles.png");
+    for mut tilemap_texture in query.iter_mut() {
+        *tilemap_texture = TilemapTexture::Single(texture_handle.clone());
+    }
 
     array_texture_loader.add(TilemapArrayTexture {
-        texture: TilemapTexture::Single(asset_server.load("tiles.png")),
-        tile_size,
+        texture: TilemapTexture::Single(texture_handle),
+        tile_size: TilemapTileSize {x: TILE_SIZE, y: TILE_SIZE},
         ..Default::default()
     });
 }
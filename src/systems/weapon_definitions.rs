@@ -0,0 +1,89 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub fn load_weapon_definitions(
+    asset_server: Res<AssetServer>,
+    mut weapon_definition_handles: ResMut<WeaponDefinitionHandles>
+) {
+    weapon_definition_handles.value = asset_server.load_folder("weapons").expect("Couldn't load weapon definitions folder");
+}
+
+pub fn stage_weapon_definition_changes( // Runs per frame, not per tick
+    mut asset_events: EventReader<AssetEvent<WeaponDefinition>>,
+    assets: Res<Assets<WeaponDefinition>>,
+    asset_server: Res<AssetServer>,
+    mut pending_weapon_definitions: ResMut<PendingWeaponDefinitions>
+) {
+    // Loading the files counts as a change too, so whatever is in assets/ overrides the built-in definitions
+    for event in asset_events.iter() {
+        let handle = match event {
+            AssetEvent::Created {handle} | AssetEvent::Modified {handle} => handle,
+            AssetEvent::Removed {..} => continue // Keep using the last definition
+        };
+        let definition_option = assets.get(handle);
+        let path_option = asset_server.get_handle_path(handle);
+        if definition_option.is_none() || path_option.is_none() {
+            continue;
+        }
+        let file_name = path_option.unwrap().path().file_name().unwrap().to_string_lossy().to_string();
+        let name = file_name.strip_suffix(".weapon.ron").unwrap_or(&file_name).to_string();
+        pending_weapon_definitions.value.push((name, definition_option.unwrap().clone()));
+    }
+}
+
+pub fn apply_weapon_definition_changes( // Runs at the start of a tick
+    mut pending_weapon_definitions: ResMut<PendingWeaponDefinitions>,
+    mut weapon_definitions: ResMut<WeaponDefinitions>,
+    mut weapon_query: Query<(
+        &Weapon,
+        &mut Gun,
+        &mut Collider,
+        &mut Mass,
+        &mut Restitution,
+        &mut FloorFriction,
+        &mut Fill,
+        &mut Stroke
+    )>
+) {
+    for (name, definition) in pending_weapon_definitions.value.drain(..) {
+        weapon_definitions.value.insert(name.clone(), definition.clone());
+
+        // Change existing weapons' properties without touching their state
+        for (
+            weapon,
+            mut gun,
+            mut collider,
+            mut mass,
+            mut restitution,
+            mut floor_friction,
+            mut fill,
+            mut stroke
+        ) in weapon_query.iter_mut() {
+            if weapon.name != name {
+                continue;
+            }
+            let mut new_gun = definition.new_gun();
+            new_gun.cooldown_timer = gun.cooldown_timer;
+            new_gun.trigger_depressed = gun.trigger_depressed;
+            new_gun.trigger_depressed_previous_frame = gun.trigger_depressed_previous_frame;
+            new_gun.rounds_in_magazine = gun.rounds_in_magazine.min(new_gun.magazine_capacity);
+            new_gun.reserve_ammo = gun.reserve_ammo;
+            new_gun.reload_timer = gun.reload_timer;
+            new_gun.heat_level = gun.heat_level;
+            new_gun.overheat_timer = gun.overheat_timer;
+            new_gun.jammed = gun.jammed;
+            *gun = new_gun;
+
+            collider.radius = definition.collider_radius;
+            mass.value = definition.mass;
+            restitution.value = definition.restitution;
+            floor_friction.value = definition.floor_friction;
+            if fill.color != Color::NONE { // Hollow while flying
+                fill.color = definition.colour;
+            }
+            stroke.color = definition.colour;
+        }
+    }
+}
@@ -1,9 +1,18 @@
 use crate::components::*;
+use crate::resources::*;
 use bevy::prelude::*;
 
+pub fn latch_key_presses( // Runs per frame, not per tick
+    mut keys_pressed_since_last_tick: ResMut<KeysPressedSinceLastTick>,
+    keyboard_input: Res<Input<KeyCode>>
+) {
+    keys_pressed_since_last_tick.value.extend(keyboard_input.get_just_pressed());
+}
+
 pub fn player_input(
     mut query: Query<&mut Will, (With<Player>, With<Alive>)>,
-    keyboard_input: Res<Input<KeyCode>>
+    keyboard_input: Res<Input<KeyCode>>,
+    mut keys_pressed_since_last_tick: ResMut<KeysPressedSinceLastTick>
 ) {
     if let Ok(mut will) = query.get_single_mut() {
         let mut target = Vec2::ZERO;
@@ -33,11 +42,16 @@ pub fn player_input(
         }
         will.target_angular_velocity_multiplier = Some(target);
 
-        will.drop = keyboard_input.just_pressed(KeyCode::Q);
-        will.pick_up = keyboard_input.just_pressed(KeyCode::F);
+        will.drop = keys_pressed_since_last_tick.value.contains(&KeyCode::Q);
+        will.throw = keys_pressed_since_last_tick.value.contains(&KeyCode::G);
+        will.melee = keys_pressed_since_last_tick.value.contains(&KeyCode::E);
+        will.pick_up = keys_pressed_since_last_tick.value.contains(&KeyCode::F);
+        will.reload = keys_pressed_since_last_tick.value.contains(&KeyCode::R);
+        will.use_item = keys_pressed_since_last_tick.value.contains(&KeyCode::H);
 
         will.depress_trigger = keyboard_input.pressed(KeyCode::Space);
     }
+    keys_pressed_since_last_tick.value.clear();
 }
 
 pub fn ai(
@@ -47,3 +61,52 @@ pub fn ai(
         
     }
 }
+
+pub fn play_back_replay(
+    mut query: Query<(&SimulationId, &mut Will)>,
+    mut replay_player: ResMut<ReplayPlayer>
+) {
+    // Wills were cleared in pre-update, so entities the replay doesn't mention this tick (or after it ends) do nothing
+    let tick = replay_player.next_tick;
+    replay_player.next_tick += 1;
+    if tick == replay_player.replay.ticks.len() {
+        println!("Replay finished");
+    }
+    if let Some(recorded_wills) = replay_player.replay.ticks.get(tick) {
+        for (simulation_id, mut will) in query.iter_mut() {
+            // Simulation IDs match those in the recording because the simulation is deterministic
+            if let Some((_, recorded_will)) = recorded_wills.iter().find(|(id, _)| *id == simulation_id.value) {
+                *will = recorded_will.clone();
+            }
+        }
+    }
+}
+
+pub fn record_wills(
+    query: Query<(&SimulationId, &Will)>,
+    mut replay_recorder: ResMut<ReplayRecorder>
+) {
+    let mut recorded_wills = Vec::new();
+    for (simulation_id, will) in query.iter() {
+        recorded_wills.push((simulation_id.value, will.clone()));
+    }
+    replay_recorder.replay.ticks.push(recorded_wills);
+}
+
+pub fn save_replay_on_exit(
+    mut app_exit_events: EventReader<bevy::app::AppExit>,
+    replay_recorder: Res<ReplayRecorder>
+) {
+    if app_exit_events.iter().next().is_some() {
+        replay_recorder.replay.save(&replay_recorder.path);
+        println!("Saved replay to {}", replay_recorder.path.display());
+    }
+}
+
+pub fn playing_back_replay(replay_player_option: Option<Res<ReplayPlayer>>) -> bool { // Run condition
+    return replay_player_option.is_some();
+}
+
+pub fn not_playing_back_replay(replay_player_option: Option<Res<ReplayPlayer>>) -> bool { // Run condition
+    return replay_player_option.is_none();
+}
@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 
+pub fn get_moment_of_inertia(mass: f32, radius: f32) -> f32 {
+    // Of a uniform disc about its centre
+    return 0.5 * mass * radius * radius;
+}
+
 pub fn circle_circle(
     a_position: Vec2, a_radius: f32, a_velocity: Vec2, a_mass: f32, a_restitution: f32,
     b_position: Vec2, b_radius: f32, b_velocity: Vec2, b_mass: f32, b_restitution: f32
@@ -60,7 +65,7 @@ pub fn circle_aabb(
             direction * distance_to_separate * b_distance_movement_share
         );
 
-        let restitution = a_restitution.min(b_restitution);
+        let restitution = (a_restitution * b_restitution).min(1.0); // Product so that both surfaces matter, letting bouncy walls liven up dull things
         let velocity_difference = b_velocity - a_velocity;
         let impact_speed = velocity_difference.dot(direction);
         let impact_speed = -impact_speed; // HACK
@@ -102,7 +107,7 @@ pub fn circle_aabb(
             direction * distance_to_separate * b_distance_movement_share
         );
 
-        let restitution = a_restitution.min(b_restitution);
+        let restitution = (a_restitution * b_restitution).min(1.0); // As above
         let velocity_difference = b_velocity - a_velocity;
         let impact_speed = velocity_difference.dot(direction);
         let impact_speed = -impact_speed; // HACK
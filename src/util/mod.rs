@@ -1,3 +1,4 @@
 pub mod random_in_shape;
 pub mod collision_detection;
 pub mod collision_resolution;
+pub mod tilemap;
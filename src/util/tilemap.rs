@@ -0,0 +1,20 @@
+use crate::systems::startup::{TILEMAP_OFFSET, TILE_SIZE};
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+pub fn get_tile_position_from_grid(tile_x: i32, tile_y: i32, tilemap_size: &TilemapSize) -> Option<TilePos> {
+    // For grid coordinates from new_grid_raycast, which aren't bounded by the tilemap
+    if !(
+        0 <= tile_x && (tile_x as u32) < tilemap_size.x &&
+        0 <= tile_y && (tile_y as u32) < tilemap_size.y
+    ) {
+        return None;
+    }
+    return Some(TilePos {x: tile_x as u32, y: tile_y as u32});
+}
+
+pub fn get_tile_position(position: Vec2, tilemap_size: &TilemapSize) -> Option<TilePos> {
+    // The tile that contains position. Tiles are centred on their grid positions
+    let grid_position = ((position - TILEMAP_OFFSET + TILE_SIZE / 2.0) / TILE_SIZE).floor();
+    return get_tile_position_from_grid(grid_position.x as i32, grid_position.y as i32, tilemap_size);
+}
@@ -0,0 +1,133 @@
+#![allow(dead_code)] // Each test crate that includes this uses a different subset of it
+// Shared harness for the integration tests. Builds a headless App with the real simulation schedule and an empty scene
+
+use bevy::prelude::*;
+use bevy::ecs::event::{Event, ManualEventReader};
+use bevy::ecs::system::CommandQueue;
+use bevy::utils::HashMap;
+use bevy_ecs_tilemap::prelude::*;
+use shootre::*;
+use shootre::components::*;
+use shootre::resources::*;
+
+#[derive(Resource, Default)]
+pub struct ScriptedWills {pub value: HashMap<Entity, Will>} // Applied every tick until changed
+
+fn apply_scripted_wills(
+    mut query: Query<&mut Will>,
+    scripted_wills: Res<ScriptedWills>
+) {
+    for (entity, scripted_will) in scripted_wills.value.iter() {
+        if let Ok(mut will) = query.get_mut(*entity) {
+            *will = scripted_will.clone();
+        }
+    }
+}
+
+pub fn new_app() -> App {
+    let mut app = App::new();
+    app
+        .add_plugins(MinimalPlugins)
+        .add_plugin(SimulationPlugin {
+            seed: 0,
+            ticks_per_second: TICKS_PER_SECOND,
+            starting_scene: StartingScene::Empty
+        })
+        .init_resource::<ScriptedWills>()
+        .add_system(apply_scripted_wills.in_set(Wills).in_schedule(CoreSchedule::FixedUpdate));
+
+    // SimulationPlugin only adds these in debug builds, but the tests should always assert them
+    #[cfg(not(debug_assertions))]
+    app.add_systems((
+        shootre::systems::physics::check_consistent_grounded_flying_state,
+        shootre::systems::hierarchy::check_consistent_hierarchy_state
+    ).in_set(ConsistentStateChecks).in_schedule(CoreSchedule::FixedUpdate));
+
+    app.setup();
+    app.update(); // Runs startup, spawning the tilemaps
+    return app;
+}
+
+pub fn spawn<F: FnOnce(&mut Commands) -> Entity>(app: &mut App, spawner: F) -> Entity {
+    let mut command_queue = CommandQueue::default();
+    let entity = spawner(&mut Commands::new(&mut command_queue, &app.world));
+    command_queue.apply(&mut app.world);
+    return entity;
+}
+
+pub fn spawn_named_weapon(app: &mut App, name: &str, position: Vec2) -> Entity {
+    // From the app's WeaponDefinitions, which are the built-in ones since tests don't load assets
+    let mut command_queue = CommandQueue::default();
+    let entity = shootre::systems::startup::spawn_weapon(
+        &mut Commands::new(&mut command_queue, &app.world),
+        app.world.resource::<WeaponDefinitions>(),
+        name,
+        position
+    );
+    command_queue.apply(&mut app.world);
+    return entity;
+}
+
+pub fn set_will(app: &mut App, entity: Entity, will: Will) {
+    app.world.resource_mut::<ScriptedWills>().value.insert(entity, will);
+}
+
+pub fn spawn_armed_shooters(app: &mut App, loadouts: &[(Vec2, &str)]) -> Vec<(Entity, Entity)> {
+    // An actor facing right for each position, picking up the named weapon there over one tick. Returns the shooters and their weapons
+    let mut shooters = Vec::new();
+    for (position, weapon_name) in loadouts.iter() {
+        let shooter = spawn(app, |commands| shootre::systems::startup::spawn_actor(commands, *position, 0.0, 40000.0));
+        let weapon = spawn_named_weapon(app, weapon_name, *position);
+        set_will(app, shooter, Will {pick_up: true, ..default()});
+        shooters.push((shooter, weapon));
+    }
+    tick(app, 1);
+    for (shooter, weapon) in shooters.iter() {
+        assert_eq!(app.world.get::<Parent>(*weapon).unwrap().get(), *shooter);
+        set_will(app, *shooter, Will {..default()});
+    }
+    return shooters;
+}
+
+pub fn fire_once(app: &mut App, shooters: &[Entity]) {
+    // Pulls every trigger for one tick, then gives the rounds a couple of ticks to land
+    for shooter in shooters.iter() {
+        set_will(app, *shooter, Will {depress_trigger: true, ..default()});
+    }
+    tick(app, 1);
+    for shooter in shooters.iter() {
+        set_will(app, *shooter, Will {..default()});
+    }
+    tick(app, 2);
+}
+
+pub fn tick(app: &mut App, ticks: u32) {
+    for _ in 0..ticks {
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+    }
+}
+
+pub fn read_event_entities<E: Event>(app: &App, reader: &mut ManualEventReader<E>, get_entity: fn(&E) -> Entity) -> Vec<Entity> {
+    // Only FixedUpdate is run, so events are never cleared and the reader sees everything since it last read
+    return reader.iter(app.world.resource::<Events<E>>()).map(get_entity).collect();
+}
+
+pub fn set_tile_texture<T: Component>(app: &mut App, tile_position: TilePos, texture_index: u32) {
+    // T is the tilemap's marker component
+    let mut tilemap_query = app.world.query_filtered::<&TileStorage, With<T>>();
+    let tile_entity = tilemap_query.single(&app.world).get(&tile_position).unwrap();
+    app.world.get_mut::<TileTextureIndex>(tile_entity).unwrap().0 = texture_index;
+}
+
+pub fn add_wall_tile(app: &mut App, tile_position: TilePos, texture_index: u32) {
+    // The wall tilemap only has tiles around the edge to begin with
+    let mut tilemap_query = app.world.query_filtered::<Entity, With<WallTilemap>>();
+    let tilemap_entity = tilemap_query.single(&app.world);
+    let tile_entity = app.world.spawn(TileBundle {
+        position: tile_position,
+        tilemap_id: TilemapId(tilemap_entity),
+        texture_index: TileTextureIndex(texture_index),
+        ..default()
+    }).id();
+    app.world.get_mut::<TileStorage>(tilemap_entity).unwrap().set(&tile_position, tile_entity);
+}
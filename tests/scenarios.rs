@@ -0,0 +1,925 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::ecs::event::ManualEventReader;
+use bevy_ecs_tilemap::prelude::TilePos;
+use shootre::TICKS_PER_SECOND;
+use shootre::components::*;
+use shootre::events::*;
+use shootre::systems::startup::*;
+use common::*;
+
+#[test]
+fn player_picks_up_fires_and_drops_shotgun() {
+    let mut app = new_app();
+    let player = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(80.0, 100.0), 0.0, 40000.0));
+    app.world.entity_mut(player).insert(Player);
+    let shotgun = spawn_named_weapon(&mut app, "shotgun", Vec2::new(95.0, 100.0));
+    let mut dropping_reader = ManualEventReader::<Dropping>::default();
+
+    set_will(&mut app, player, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(shotgun).unwrap().get(), player);
+    assert!(app.world.get::<HoldingInfo>(shotgun).is_some());
+    assert!(app.world.get::<Position>(shotgun).is_none());
+
+    set_will(&mut app, player, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    let mut projectile_query = app.world.query_filtered::<(), With<GunProjectile>>();
+    assert_eq!(projectile_query.iter(&app.world).count(), 10);
+
+    set_will(&mut app, player, Will {drop: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(read_event_entities(&app, &mut dropping_reader, |event| event.entity), vec![shotgun]);
+    assert!(app.world.get::<Parent>(shotgun).is_none());
+    assert!(app.world.get::<HoldingInfo>(shotgun).is_none());
+    assert!(app.world.get::<Position>(shotgun).is_some());
+    assert!(app.world.get::<Children>(player).is_none());
+}
+
+#[test]
+fn cannon_round_gibs_actor() {
+    let mut app = new_app();
+    let shooter = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 120.0), 0.0, 40000.0)); // Far enough from the wall behind that the recoil doesn't slam it there yet
+    let cannon = spawn_named_weapon(&mut app, "ship_cannon", Vec2::new(100.0, 120.0));
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(160.0, 120.0), 0.0, 4000.0));
+    let mut death_reader = ManualEventReader::<Death>::default();
+    let mut gibbing_reader = ManualEventReader::<Gibbing>::default();
+
+    set_will(&mut app, shooter, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(cannon).unwrap().get(), shooter);
+
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 2);
+    assert!(read_event_entities(&app, &mut gibbing_reader, |event| event.entity).contains(&target));
+    assert!(read_event_entities(&app, &mut death_reader, |event| event.entity).contains(&target));
+    assert!(app.world.get_entity(target).is_none());
+    assert!(app.world.get::<Alive>(shooter).is_some());
+    let mut gib_query = app.world.query_filtered::<&AngularVelocity, With<Gib>>();
+    assert!(gib_query.iter(&app.world).count() > 0);
+    assert!(gib_query.iter(&app.world).all(|angular_velocity| angular_velocity.value != 0.0));
+}
+
+#[test]
+fn tripping_body_bounces_off_wall() {
+    let mut app = new_app();
+    let actor = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 120.0), 0.0, 4000.0));
+    app.world.get_mut::<Velocity>(actor).unwrap().value = Vec2::new(400.0, 0.0);
+
+    tick(&mut app, 1);
+    assert!(app.world.get::<Flying>(actor).is_some());
+    assert!(app.world.get::<Grounded>(actor).is_none());
+
+    tick(&mut app, (TICKS_PER_SECOND / 2.0) as u32);
+    // Interior of the 20x20 walled map
+    let wall_inner_edge = TILEMAP_OFFSET.x + TILE_SIZE * 18.5;
+    let position = app.world.get::<Position>(actor).unwrap().value;
+    assert!(position.x < wall_inner_edge);
+    assert!(app.world.get::<Velocity>(actor).unwrap().value.x <= 0.0);
+}
+
+#[test]
+fn crowd_of_actors_stays_separated() {
+    let mut app = new_app();
+    let mut actors = Vec::new();
+    for x in 0..4 {
+        for y in 0..4 {
+            let position = Vec2::new(80.0 + x as f32 * 12.0, 100.0 + y as f32 * 12.0); // Overlapping, since radii are 10
+            actors.push(spawn(&mut app, |commands| spawn_actor(commands, position, 0.0, 4000.0)));
+        }
+    }
+
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    for (i, a) in actors.iter().enumerate() {
+        for b in actors[i + 1..].iter() {
+            let a_position = app.world.get::<Position>(*a).unwrap().value;
+            let b_position = app.world.get::<Position>(*b).unwrap().value;
+            assert!(a_position.distance(b_position) > 15.0);
+        }
+    }
+}
+
+#[test]
+fn fast_bodies_do_not_tunnel_through_walls() {
+    let mut app = new_app();
+    let mut bodies = Vec::new();
+    for (i, velocity) in [
+        Vec2::new(20000.0, 0.0),
+        Vec2::new(-20000.0, 0.0),
+        Vec2::new(0.0, 20000.0),
+        Vec2::new(0.0, -20000.0),
+        Vec2::new(15000.0, 13000.0)
+    ].iter().enumerate() {
+        let body = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0 + i as f32 * 25.0, 120.0), 0.0, 4000.0));
+        app.world.get_mut::<Velocity>(body).unwrap().value = *velocity;
+        app.world.entity_mut(body).remove::<Hits>(); // Would be gibbed by the impact otherwise
+        bodies.push(body);
+    }
+
+    let interior_lower = TILEMAP_OFFSET + TILE_SIZE / 2.0;
+    let interior_upper = TILEMAP_OFFSET + TILE_SIZE * 18.5;
+    for _ in 0..TICKS_PER_SECOND as u32 {
+        tick(&mut app, 1);
+        for body in bodies.iter() {
+            let position = app.world.get::<Position>(*body).unwrap().value;
+            assert!(interior_lower.x < position.x && position.x < interior_upper.x);
+            assert!(interior_lower.y < position.y && position.y < interior_upper.y);
+        }
+    }
+}
+
+#[test]
+fn slamming_into_wall_hurts_and_kills() {
+    let mut app = new_app();
+    let slow = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(140.0, 80.0), 0.0, 4000.0));
+    let fast = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(140.0, 160.0), 0.0, 4000.0));
+    app.world.get_mut::<Velocity>(slow).unwrap().value = Vec2::new(400.0, 0.0);
+    app.world.get_mut::<Velocity>(fast).unwrap().value = Vec2::new(3000.0, 0.0);
+    let mut death_reader = ManualEventReader::<Death>::default();
+    let mut gibbing_reader = ManualEventReader::<Gibbing>::default();
+
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    let deaths = read_event_entities(&app, &mut death_reader, |event| event.entity);
+    assert_eq!(deaths, vec![fast]);
+    assert!(read_event_entities(&app, &mut gibbing_reader, |event| event.entity).is_empty());
+    assert!(app.world.get::<Dead>(fast).is_some());
+    assert!(app.world.get::<ContainedBlood>(fast).unwrap().amount < 1000.0);
+
+    let slow_health = app.world.get::<Health>(slow).unwrap();
+    assert!(0.0 < slow_health.current && slow_health.current < slow_health.maximum);
+    assert!(app.world.get::<Alive>(slow).is_some());
+}
+
+#[test]
+fn ice_floor_lets_items_slide_further() {
+    let mut app = new_app();
+    for x in 1..19 {
+        set_tile_texture::<MainTilemap>(&mut app, TilePos {x: x, y: 14}, 4); // Ice
+    }
+    let on_concrete = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(60.0, 80.0));
+    let on_ice = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(60.0, TILEMAP_OFFSET.y + TILE_SIZE * 14.0));
+    app.world.get_mut::<Velocity>(on_concrete).unwrap().value = Vec2::new(100.0, 0.0); // Under the default trip threshold
+    app.world.get_mut::<Velocity>(on_ice).unwrap().value = Vec2::new(100.0, 0.0);
+
+    tick(&mut app, TICKS_PER_SECOND as u32 / 2);
+    assert_eq!(app.world.get::<Velocity>(on_concrete).unwrap().value, Vec2::ZERO);
+    assert!(app.world.get::<Velocity>(on_ice).unwrap().value.x > 60.0);
+}
+
+#[test]
+fn walking_on_ice_gets_up_to_speed_slower() {
+    let mut app = new_app();
+    for x in 1..19 {
+        set_tile_texture::<MainTilemap>(&mut app, TilePos {x: x, y: 14}, 4); // Ice
+    }
+    let on_concrete = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 80.0), 0.0, 40000.0));
+    let on_ice = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, TILEMAP_OFFSET.y + TILE_SIZE * 14.0), 0.0, 40000.0));
+    let walk_right = Will {target_relative_velocity_multiplier: Some(Vec2::new(0.0, 1.0)), ..default()}; // Forwards, and both face right
+    set_will(&mut app, on_concrete, walk_right.clone());
+    set_will(&mut app, on_ice, walk_right);
+
+    tick(&mut app, TICKS_PER_SECOND as u32 / 10);
+    let concrete_speed = app.world.get::<Velocity>(on_concrete).unwrap().value.x;
+    let ice_speed = app.world.get::<Velocity>(on_ice).unwrap().value.x;
+    assert!(concrete_speed > 0.0 && ice_speed > 0.0);
+    assert!(ice_speed < concrete_speed / 2.0);
+}
+
+#[test]
+fn rubber_walls_bounce_things_back_harder_than_concrete() {
+    let mut app = new_app();
+    for y in 12..17 {
+        set_tile_texture::<WallTilemap>(&mut app, TilePos {x: 0, y: y}, 3); // Rubber
+    }
+    let at_concrete = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(50.0, 80.0));
+    let at_rubber = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(50.0, TILEMAP_OFFSET.y + TILE_SIZE * 14.0));
+    app.world.get_mut::<Velocity>(at_concrete).unwrap().value = Vec2::new(-150.0, 0.0);
+    app.world.get_mut::<Velocity>(at_rubber).unwrap().value = Vec2::new(-150.0, 0.0);
+
+    let mut ticks = 0;
+    while app.world.get::<Velocity>(at_concrete).unwrap().value.x <= 0.0 {
+        tick(&mut app, 1);
+        ticks += 1;
+        assert!(ticks < TICKS_PER_SECOND as u32);
+    }
+    // Both hit on the same tick, and a machine gun's own restitution is what concrete leaves it with
+    assert!(app.world.get::<Velocity>(at_rubber).unwrap().value.x > app.world.get::<Velocity>(at_concrete).unwrap().value.x * 2.0);
+}
+
+#[test]
+fn projectiles_pass_through_grating_and_stop_at_concrete() {
+    let mut app = new_app();
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(100.0, 120.0), "machine_gun")])[0];
+    for y in 0..20 {
+        set_tile_texture::<WallTilemap>(&mut app, TilePos {x: 19, y: y}, 5); // Metal grating on the right wall only
+    }
+
+    fire_once(&mut app, &[shooter]);
+
+    let mut projectile_query = app.world.query_filtered::<(&Position, &Velocity), With<GunProjectile>>();
+    let (position, velocity) = projectile_query.single(&app.world);
+    assert!(position.value.x > TILEMAP_OFFSET.x + TILE_SIZE * 19.5);
+    assert!(velocity.value.x < 8000.0 * 0.8 + 1.0);
+
+    // Turned around to face the concrete left wall
+    app.world.get_mut::<Angle>(shooter).unwrap().value = std::f32::consts::PI;
+    fire_once(&mut app, &[shooter]);
+    let mut left_query = app.world.query_filtered::<&Position, With<GunProjectile>>();
+    assert!(left_query.iter(&app.world).all(|position| position.value.x > TILEMAP_OFFSET.x - TILE_SIZE / 2.0));
+}
+
+#[test]
+fn off_centre_hits_spin_targets() {
+    let mut app = new_app();
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "machine_gun")])[0];
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(130.0, 126.0), 0.0, 40000.0)); // Shot below its centre
+
+    fire_once(&mut app, &[shooter]);
+
+    // Pushed right at a point below the centre turns it anticlockwise
+    assert!(app.world.get::<Position>(target).unwrap().value.x > 130.0);
+    assert!(app.world.get::<Angle>(target).unwrap().value > 0.0);
+}
+
+#[test]
+fn machine_gun_runs_dry_then_reloads_from_pickup() {
+    let mut app = new_app();
+    let (shooter, machine_gun) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "machine_gun")])[0];
+    let mut dry_fire_reader = ManualEventReader::<DryFire>::default();
+    {
+        let mut gun = app.world.get_mut::<Gun>(machine_gun).unwrap();
+        gun.rounds_in_magazine = 2;
+        gun.reserve_ammo = 0;
+    }
+
+    // Holding the trigger empties the magazine without dry-firing, pulling it again dry-fires
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32 / 2);
+    assert_eq!(app.world.get::<Gun>(machine_gun).unwrap().rounds_in_magazine, 0);
+    assert!(read_event_entities(&app, &mut dry_fire_reader, |event| event.gun).is_empty());
+    set_will(&mut app, shooter, Will {..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(read_event_entities(&app, &mut dry_fire_reader, |event| event.gun), vec![machine_gun]);
+
+    // Nothing to reload with
+    set_will(&mut app, shooter, Will {reload: true, ..default()});
+    tick(&mut app, 1);
+    assert!(app.world.get::<Gun>(machine_gun).unwrap().reload_timer.is_none());
+
+    // Picking up ammo while holding the gun fills its reserve
+    let ammo_pickup = spawn(&mut app, |commands| spawn_ammo_pickup(commands, Vec2::new(65.0, 120.0), 60));
+    set_will(&mut app, shooter, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert!(app.world.get_entity(ammo_pickup).is_none());
+    assert_eq!(app.world.get::<Gun>(machine_gun).unwrap().reserve_ammo, 60);
+    assert_eq!(app.world.get::<Parent>(machine_gun).unwrap().get(), shooter);
+
+    // Can't shoot while reloading
+    set_will(&mut app, shooter, Will {reload: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    let gun = app.world.get::<Gun>(machine_gun).unwrap();
+    assert!(gun.reload_timer.is_some());
+    assert_eq!(gun.rounds_in_magazine, 0);
+
+    set_will(&mut app, shooter, Will {..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32 * 2);
+    let gun = app.world.get::<Gun>(machine_gun).unwrap();
+    assert!(gun.reload_timer.is_none());
+    assert_eq!(gun.rounds_in_magazine, 30);
+    assert_eq!(gun.reserve_ammo, 30);
+}
+
+#[test]
+fn cannon_recoil_knocks_shooter_flying() {
+    let mut app = new_app();
+    let armed = spawn_armed_shooters(&mut app, &[(Vec2::new(100.0, 120.0), "ship_cannon"), (Vec2::new(100.0, 180.0), "machine_gun")]);
+    let (shooter, machine_gunner) = (armed[0].0, armed[1].0);
+    app.world.get_mut::<Angle>(shooter).unwrap().value = std::f32::consts::PI;
+
+    // Only the firing tick, since walking brakes the machine gunner straight after
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    set_will(&mut app, machine_gunner, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    // Fired leftwards, so pushed rightwards and, being gripped to the right, turned clockwise
+    assert!(app.world.get::<Velocity>(shooter).unwrap().value.x > 2000.0);
+    assert!(app.world.get::<AngularVelocity>(shooter).unwrap().value < 0.0);
+    assert!(app.world.get::<Flying>(shooter).is_some());
+    // A machine gun round isn't enough to trip anyone
+    let machine_gunner_velocity = app.world.get::<Velocity>(machine_gunner).unwrap().value;
+    assert!(machine_gunner_velocity.x < 0.0 && machine_gunner_velocity.x > -10.0);
+    assert!(app.world.get::<Grounded>(machine_gunner).is_some());
+}
+
+#[test]
+fn cannon_round_punches_through_a_line_of_actors() {
+    let mut app = new_app();
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(50.0, 120.0), "ship_cannon")])[0];
+    let mut line = Vec::new();
+    for i in 0..4 {
+        line.push(spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(95.0 + i as f32 * 22.0, 120.0), 0.0, 4000.0)));
+    }
+    let mut gibbing_reader = ManualEventReader::<Gibbing>::default();
+
+    fire_once(&mut app, &[shooter]);
+    let gibbed = read_event_entities(&app, &mut gibbing_reader, |event| event.entity);
+    for actor in line.iter() {
+        assert!(gibbed.contains(actor));
+    }
+}
+
+#[test]
+fn machine_gun_round_stops_in_first_body_but_grazes_through() {
+    let mut app = new_app();
+    let shooters: Vec<Entity> = spawn_armed_shooters(&mut app, &[(Vec2::new(50.0, 80.0), "machine_gun"), (Vec2::new(50.0, 160.0), "machine_gun")])
+        .iter().map(|(shooter, _)| *shooter).collect();
+    let front = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 80.0), 0.0, 40000.0));
+    let behind = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(140.0, 80.0), 0.0, 40000.0));
+    let grazed = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 169.0), 0.0, 40000.0)); // Only clipped by the round
+    let behind_grazed = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(140.0, 160.0), 0.0, 40000.0));
+
+    fire_once(&mut app, &shooters);
+    assert!(app.world.get::<Health>(front).unwrap().current < 1.0);
+    assert_eq!(app.world.get::<Health>(behind).unwrap().current, 1.0);
+    assert!(app.world.get::<Health>(grazed).unwrap().current < 1.0);
+    assert!(app.world.get::<Health>(behind_grazed).unwrap().current < 1.0);
+}
+
+#[test]
+fn exit_wounds_land_on_the_tick_the_projectile_comes_out() {
+    let mut app = new_app();
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(125.0, 120.0), 0.0, 40000.0));
+    // Goes 25 units a tick, so it's still inside the target at the end of the tick it goes in
+    let projectile = app.world.spawn((
+        Position {value: Vec2::new(100.0, 120.0)},
+        PreviousPosition {value: Vec2::new(100.0, 120.0)},
+        Velocity {value: Vec2::new(25.0 * TICKS_PER_SECOND, 0.0)},
+        Mass {value: 0.01},
+        Flying,
+        GunProjectile,
+        ProjectileColour {value: Color::WHITE},
+        BaseDamagePerSpeed {value: 0.0},
+        Penetration {power: 100.0, penetrated: Vec::new(), inside: Vec::new()}
+    )).id();
+
+    tick(&mut app, 1);
+    let hits = &app.world.get::<Hits>(target).unwrap().value;
+    assert_eq!(hits.len(), 1);
+    assert!(!hits[0].exit_wound);
+    assert_eq!(app.world.get::<Penetration>(projectile).unwrap().inside, vec![target]);
+
+    tick(&mut app, 1);
+    let hits = &app.world.get::<Hits>(target).unwrap().value;
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].exit_wound);
+    assert!((hits[0].entry_point.x - 135.0).abs() < 0.5); // The far side, not past it
+    assert!(app.world.get::<Penetration>(projectile).unwrap().inside.is_empty());
+}
+
+#[test]
+fn glancing_shots_ricochet_off_walls_but_steep_ones_stop() {
+    let mut app = new_app();
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 190.0), "machine_gun")])[0];
+    app.world.get_mut::<Angle>(shooter).unwrap().value = 0.2; // Skimming along under the top wall
+
+    // Only the firing tick, so that the bounced rounds are still in flight
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, shooter, Will {..default()});
+
+    let mut projectile_query = app.world.query_filtered::<(&Position, &Velocity), With<GunProjectile>>();
+    let bounced: Vec<(Vec2, Vec2)> = projectile_query.iter(&app.world)
+        .map(|(position, velocity)| (position.value, velocity.value))
+        .filter(|(_, velocity)| velocity.y < 0.0)
+        .collect();
+    assert!(!bounced.is_empty());
+    for (position, velocity) in bounced {
+        assert!(position.y < TILEMAP_OFFSET.y + TILE_SIZE * 18.5);
+        assert!(velocity.length() < 8000.0 * 0.7);
+    }
+
+    // Straight up into the wall
+    tick(&mut app, 60);
+    app.world.get_mut::<Angle>(shooter).unwrap().value = std::f32::consts::FRAC_PI_2;
+    fire_once(&mut app, &[shooter]);
+    let mut velocity_query = app.world.query_filtered::<&Velocity, With<GunProjectile>>();
+    assert!(velocity_query.iter(&app.world).all(|velocity| velocity.value.y >= 0.0));
+}
+
+#[test]
+fn railgun_hits_instantly_without_projectiles_and_leaves_a_fading_beam() {
+    let mut app = new_app();
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "railgun")])[0];
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(150.0, 120.0), 0.0, 40000.0));
+
+    fire_once(&mut app, &[shooter]);
+    let health = app.world.get::<Health>(target).unwrap();
+    assert!(health.current < health.maximum);
+    assert!(app.world.get::<Velocity>(target).unwrap().value.x > 0.0);
+    let mut projectile_query = app.world.query_filtered::<(), With<GunProjectile>>();
+    assert_eq!(projectile_query.iter(&app.world).count(), 0);
+    let mut beam_query = app.world.query::<&Beam>();
+    assert!(beam_query.iter(&app.world).any(|beam| beam.start.x < 150.0 && beam.end.x > 150.0)); // Through the target and on to the wall
+
+    tick(&mut app, (TICKS_PER_SECOND * 0.2) as u32);
+    assert_eq!(beam_query.iter(&app.world).count(), 0);
+}
+
+#[test]
+fn railgun_hits_targets_where_they_were_at_the_start_of_the_tick() {
+    let mut app = new_app();
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "railgun")])[0];
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(150.0, 115.0), 0.0, 40000.0));
+    tick(&mut app, 1); // Into the spatial hash
+    app.world.get_mut::<Velocity>(target).unwrap().value = Vec2::new(0.0, 3000.0); // Out of the beam's spatial hash cells and path during the firing tick
+
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    assert!(app.world.get::<Position>(target).unwrap().value.y > 128.0);
+    assert_eq!(app.world.get::<LastDamage>(target).unwrap().source, Some(shooter));
+    assert!(app.world.get::<Velocity>(target).unwrap().value.x > 0.0);
+}
+
+#[test]
+fn grenade_blast_gibs_those_close_by_but_walls_shield_others() {
+    let mut app = new_app();
+    let holder = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let grenade = spawn(&mut app, |commands| spawn_grenade(commands, Vec2::new(60.0, 120.0)));
+    let bystander = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(90.0, 120.0), 0.0, 40000.0));
+    let shielded = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 160.0), 0.0, 40000.0));
+    for x in 1..19 {
+        add_wall_tile(&mut app, TilePos {x: x, y: 11}, 2); // Between the grenade and the shielded actor
+    }
+    let mut gibbing_reader = ManualEventReader::<Gibbing>::default();
+
+    set_will(&mut app, holder, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(grenade).unwrap().get(), holder);
+    set_will(&mut app, holder, Will {depress_trigger: true, ..default()}); // Lights the fuse
+    tick(&mut app, 1);
+    set_will(&mut app, holder, Will {..default()});
+    tick(&mut app, (TICKS_PER_SECOND * 2.5) as u32);
+    assert!(app.world.get_entity(grenade).is_some()); // Still cooking
+
+    tick(&mut app, (TICKS_PER_SECOND * 1.0) as u32);
+    assert!(app.world.get_entity(grenade).is_none());
+    let gibbed = read_event_entities(&app, &mut gibbing_reader, |event| event.entity);
+    assert!(gibbed.contains(&holder));
+    assert!(gibbed.contains(&bystander));
+    assert!(!gibbed.contains(&shielded));
+    assert!(app.world.get::<Alive>(shielded).is_some());
+    let health = app.world.get::<Health>(shielded).unwrap();
+    assert_eq!(health.current, health.maximum);
+}
+
+#[test]
+fn blasts_push_loose_items_but_not_held_ones() {
+    let mut app = new_app();
+    let holder = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let grenade = spawn(&mut app, |commands| spawn_grenade(commands, Vec2::new(60.0, 120.0)));
+    let loose_gun = spawn_named_weapon(&mut app, "shotgun", Vec2::new(80.0, 120.0));
+    let held_gun = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(60.0, 160.0));
+    let far_holder = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 160.0), std::f32::consts::FRAC_PI_2, 40000.0)); // Facing the blast, so not hit in the arms
+    app.world.get_mut::<Health>(far_holder).unwrap().current = 100.0;
+    set_will(&mut app, holder, Will {pick_up: true, ..default()});
+    set_will(&mut app, far_holder, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(grenade).unwrap().get(), holder);
+    assert_eq!(app.world.get::<Parent>(held_gun).unwrap().get(), far_holder);
+    app.world.get_mut::<Fuse>(grenade).unwrap().time_left = Some(0.0); // Goes off next tick
+
+    set_will(&mut app, holder, Will {..default()});
+    set_will(&mut app, far_holder, Will {..default()});
+    tick(&mut app, 1);
+    assert!(app.world.get_entity(grenade).is_none());
+    assert!(app.world.get::<Velocity>(loose_gun).unwrap().value.x > 100.0); // Away from the blast
+    assert_eq!(app.world.get::<Parent>(held_gun).unwrap().get(), far_holder);
+}
+
+#[test]
+fn launched_grenade_detonates_on_impact() {
+    let mut app = new_app();
+    let shooter = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let launcher = spawn_named_weapon(&mut app, "grenade_launcher", Vec2::new(60.0, 120.0));
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(130.0, 120.0), 0.0, 40000.0));
+    let mut gibbing_reader = ManualEventReader::<Gibbing>::default();
+
+    set_will(&mut app, shooter, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(launcher).unwrap().get(), shooter);
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, shooter, Will {..default()});
+    tick(&mut app, (TICKS_PER_SECOND * 0.5) as u32);
+
+    let mut explosive_query = app.world.query_filtered::<(), With<Explosive>>();
+    assert_eq!(explosive_query.iter(&app.world).count(), 0);
+    let gibbed = read_event_entities(&app, &mut gibbing_reader, |event| event.entity);
+    assert!(gibbed.contains(&target));
+    assert!(!gibbed.contains(&shooter));
+    assert!(app.world.get::<Alive>(shooter).is_some());
+}
+
+#[test]
+fn thrown_empty_gun_hurts_who_it_hits_but_not_the_thrower() {
+    let mut app = new_app();
+    let thrower = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let machine_gun = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(60.0, 120.0));
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(110.0, 120.0), 0.0, 40000.0));
+    app.world.get_mut::<Gun>(machine_gun).unwrap().rounds_in_magazine = 0;
+
+    set_will(&mut app, thrower, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(machine_gun).unwrap().get(), thrower);
+    set_will(&mut app, thrower, Will {throw: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, thrower, Will {..default()});
+    assert!(app.world.get::<Parent>(machine_gun).is_none());
+    assert!(app.world.get::<Flying>(machine_gun).is_some());
+    assert!(app.world.get::<Thrown>(machine_gun).is_some());
+    assert!(app.world.get::<Velocity>(machine_gun).unwrap().value.x > 200.0);
+
+    tick(&mut app, (TICKS_PER_SECOND * 0.5) as u32);
+    let target_health = app.world.get::<Health>(target).unwrap();
+    assert!(target_health.current < target_health.maximum);
+    let thrower_health = app.world.get::<Health>(thrower).unwrap();
+    assert_eq!(thrower_health.current, thrower_health.maximum);
+
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    assert!(app.world.get::<Thrown>(machine_gun).is_none()); // Landed
+}
+
+#[test]
+fn melee_hits_in_front_with_a_cooldown_and_bats_hit_harder() {
+    let mut app = new_app();
+    let puncher = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let in_front = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(82.0, 120.0), 0.0, 40000.0));
+    let behind = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(38.0, 120.0), 0.0, 40000.0));
+
+    set_will(&mut app, puncher, Will {melee: true, ..default()}); // Held down, so the cooldown is what limits it
+    tick(&mut app, 1);
+    let punched_health = app.world.get::<Health>(in_front).unwrap().current;
+    assert!(punched_health < 1.0);
+    assert!(app.world.get::<Velocity>(in_front).unwrap().value.x > 0.0);
+    assert_eq!(app.world.get::<Health>(behind).unwrap().current, 1.0);
+    assert_eq!(app.world.get::<Health>(puncher).unwrap().current, 1.0);
+    tick(&mut app, 2);
+    assert_eq!(app.world.get::<Health>(in_front).unwrap().current, punched_health);
+
+    // A bat is heavier than a fist
+    let mut app = new_app();
+    let batter = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let bat = spawn(&mut app, |commands| spawn_bat(commands, Vec2::new(60.0, 120.0)));
+    let batted = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(90.0, 120.0), 0.0, 40000.0)); // Out of a fist's reach
+    set_will(&mut app, batter, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(bat).unwrap().get(), batter);
+    set_will(&mut app, batter, Will {melee: true, ..default()});
+    tick(&mut app, 1);
+    assert!(app.world.get::<Health>(batted).unwrap().current < punched_health);
+}
+
+#[test]
+fn machine_gun_overheats_then_cools_down() {
+    let mut app = new_app();
+    let (shooter, machine_gun) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "machine_gun")])[0];
+    {
+        let mut gun = app.world.get_mut::<Gun>(machine_gun).unwrap();
+        gun.heat.as_mut().unwrap().jam_chance = 0.0;
+        gun.heat_level = 0.5; // So that it overheats before the magazine runs out
+    }
+
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    let mut ticks = 0;
+    while app.world.get::<Gun>(machine_gun).unwrap().overheat_timer.is_none() {
+        tick(&mut app, 1);
+        ticks += 1;
+        assert!(ticks < TICKS_PER_SECOND as u32 * 5);
+    }
+    let rounds_when_overheated = app.world.get::<Gun>(machine_gun).unwrap().rounds_in_magazine;
+    assert!(rounds_when_overheated > 0);
+
+    tick(&mut app, TICKS_PER_SECOND as u32); // Trigger still held
+    assert_eq!(app.world.get::<Gun>(machine_gun).unwrap().rounds_in_magazine, rounds_when_overheated);
+    tick(&mut app, (TICKS_PER_SECOND * 1.5) as u32);
+    assert!(app.world.get::<Gun>(machine_gun).unwrap().rounds_in_magazine < rounds_when_overheated);
+}
+
+#[test]
+fn jammed_gun_dry_fires_until_reloaded() {
+    let mut app = new_app();
+    let (shooter, machine_gun) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "machine_gun")])[0];
+    let mut dry_fire_reader = ManualEventReader::<DryFire>::default();
+    {
+        let mut gun = app.world.get_mut::<Gun>(machine_gun).unwrap();
+        gun.heat.as_mut().unwrap().jam_chance = 10.0; // Certain once warm
+        gun.heat_level = 0.5;
+    }
+
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    assert!(app.world.get::<Gun>(machine_gun).unwrap().jammed);
+    assert_eq!(app.world.get::<Gun>(machine_gun).unwrap().rounds_in_magazine, 29);
+    set_will(&mut app, shooter, Will {..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(read_event_entities(&app, &mut dry_fire_reader, |event| event.gun), vec![machine_gun]);
+    assert_eq!(app.world.get::<Gun>(machine_gun).unwrap().rounds_in_magazine, 29);
+
+    set_will(&mut app, shooter, Will {reload: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, shooter, Will {..default()});
+    tick(&mut app, (TICKS_PER_SECOND * 3.0) as u32);
+    assert!(!app.world.get::<Gun>(machine_gun).unwrap().jammed);
+}
+
+#[test]
+fn headshots_hurt_most_arm_hits_disarm_and_leg_hits_slow() {
+    let mut app = new_app();
+    let facing = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 80.0), std::f32::consts::PI, 40000.0)); // Towards its shooter
+    let side_on = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 120.0), -std::f32::consts::FRAC_PI_2, 40000.0));
+    let facing_away = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 160.0), 0.0, 40000.0));
+    let side_on_gun = spawn_named_weapon(&mut app, "shotgun", Vec2::new(100.0, 120.0));
+    set_will(&mut app, side_on, Will {pick_up: true, ..default()}); // Alongside the shooters
+    let shooters: Vec<Entity> = spawn_armed_shooters(&mut app, &[80.0, 120.0, 160.0].map(|y| (Vec2::new(50.0, y), "machine_gun")))
+        .iter().map(|(shooter, _)| *shooter).collect();
+    assert_eq!(app.world.get::<Parent>(side_on_gun).unwrap().get(), side_on);
+
+    fire_once(&mut app, &shooters);
+    let head_damage = 1.0 - app.world.get::<Health>(facing).unwrap().current;
+    let leg_damage = 1.0 - app.world.get::<Health>(facing_away).unwrap().current;
+    assert!(leg_damage > 0.0);
+    assert!(head_damage > leg_damage * 4.0);
+    assert!(app.world.get::<Parent>(side_on_gun).is_none());
+    assert!(app.world.get::<LegInjury>(facing_away).unwrap().speed_multiplier < 1.0);
+    assert_eq!(app.world.get::<LegInjury>(facing).unwrap().speed_multiplier, 1.0);
+    assert_eq!(app.world.get::<Gait>(facing_away).unwrap().standing_max_speed, 200.0);
+}
+
+#[test]
+fn repeated_leg_hits_slow_down_to_a_floor() {
+    let mut app = new_app();
+    let target = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(130.0, 120.0), 0.0, 40000.0)); // Facing away from its shooter
+    app.world.get_mut::<Health>(target).unwrap().current = 100.0;
+    app.world.get_mut::<Health>(target).unwrap().maximum = 100.0;
+    let (shooter, _) = spawn_armed_shooters(&mut app, &[(Vec2::new(60.0, 120.0), "machine_gun")])[0];
+
+    set_will(&mut app, shooter, Will {depress_trigger: true, ..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    set_will(&mut app, shooter, Will {..default()});
+    tick(&mut app, 2);
+    assert!(app.world.get::<Alive>(target).is_some());
+    let leg_injury = app.world.get::<LegInjury>(target).unwrap();
+    assert_eq!(leg_injury.speed_multiplier, leg_injury.minimum_speed_multiplier);
+    assert_eq!(app.world.get::<Gait>(target).unwrap().standing_max_speed, 200.0);
+
+    // Still walks, at the slowed speed
+    set_will(&mut app, target, Will {target_relative_velocity_multiplier: Some(Vec2::new(1.0, 0.0)), ..default()}); // Sideways, down the map
+    tick(&mut app, TICKS_PER_SECOND as u32 / 2);
+    assert!((app.world.get::<Velocity>(target).unwrap().value.length() - 200.0 * 0.4).abs() < 1.0);
+}
+
+#[test]
+fn bullet_wounds_bleed_until_they_clot_or_the_body_bleeds_out() {
+    let mut app = new_app();
+    let clotting = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 80.0), 0.0, 40000.0)); // Backs turned, so leg hits
+    let not_clotting = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 160.0), 0.0, 40000.0));
+    app.world.get_mut::<Wounds>(not_clotting).unwrap().clotting_rate = None;
+    for target in [clotting, not_clotting] {
+        *app.world.get_mut::<Health>(target).unwrap() = Health {maximum: 100.0, current: 100.0}; // Survives the shot itself
+    }
+    let shooters: Vec<Entity> = spawn_armed_shooters(&mut app, &[80.0, 160.0].map(|y| (Vec2::new(50.0, y), "machine_gun")))
+        .iter().map(|(shooter, _)| *shooter).collect();
+    fire_once(&mut app, &shooters);
+    for target in [clotting, not_clotting] {
+        assert_eq!(app.world.get::<Wounds>(target).unwrap().value.len(), 1);
+        assert!(app.world.get::<ContainedBlood>(target).unwrap().leak_rate > 0.0);
+        assert!(app.world.get::<Alive>(target).is_some());
+    }
+    let blood_after_hit = app.world.get::<ContainedBlood>(clotting).unwrap().amount;
+
+    tick(&mut app, TICKS_PER_SECOND as u32 * 20);
+    assert!(app.world.get::<Wounds>(clotting).unwrap().value.is_empty());
+    assert_eq!(app.world.get::<ContainedBlood>(clotting).unwrap().leak_rate, 0.0);
+    assert!(app.world.get::<ContainedBlood>(clotting).unwrap().amount < blood_after_hit);
+    assert!(app.world.get::<Alive>(clotting).is_some());
+    let mut blood_pool_query = app.world.query::<&BloodPool>();
+    assert!(blood_pool_query.iter(&app.world).count() > 0);
+
+    tick(&mut app, TICKS_PER_SECOND as u32 * 30);
+    assert!(app.world.get::<Dead>(not_clotting).is_some());
+    assert!(app.world.get::<Health>(not_clotting).unwrap().current > 0.0); // Bled out
+}
+
+#[test]
+fn medkit_heals_and_stops_bleeding_after_its_use_time() {
+    let mut app = new_app();
+    let patient = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 120.0), 0.0, 40000.0));
+    let medkit = spawn(&mut app, |commands| spawn_medkit(commands, Vec2::new(100.0, 120.0)));
+    app.world.get_mut::<Health>(patient).unwrap().current = 0.3;
+    app.world.get_mut::<ContainedBlood>(patient).unwrap().amount = 800.0;
+    app.world.get_mut::<Wounds>(patient).unwrap().value.push(Wound {bleed_rate: 5.0});
+    app.world.get_mut::<LegInjury>(patient).unwrap().speed_multiplier = 0.5;
+    set_will(&mut app, patient, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(medkit).unwrap().get(), patient);
+
+    set_will(&mut app, patient, Will {use_item: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, patient, Will {..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    assert_eq!(app.world.get::<Health>(patient).unwrap().current, 0.3);
+    assert!(app.world.get::<ContainedBlood>(patient).unwrap().leak_rate > 0.0);
+
+    tick(&mut app, TICKS_PER_SECOND as u32 * 2);
+    assert_eq!(app.world.get::<LegInjury>(patient).unwrap().speed_multiplier, 1.0);
+    assert!(app.world.get_entity(medkit).is_none());
+    assert!(app.world.get::<Children>(patient).map_or(true, |children| children.is_empty()));
+    assert_eq!(app.world.get::<Health>(patient).unwrap().current, 1.0);
+    assert!(app.world.get::<ContainedBlood>(patient).unwrap().amount > 1000.0 - 1.0);
+    assert!(app.world.get::<Wounds>(patient).unwrap().value.is_empty());
+    assert_eq!(app.world.get::<ContainedBlood>(patient).unwrap().leak_rate, 0.0);
+}
+
+#[test]
+fn dropping_a_bandage_mid_use_abandons_it() {
+    let mut app = new_app();
+    let patient = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 120.0), 0.0, 40000.0));
+    let bandage = spawn(&mut app, |commands| spawn_bandage(commands, Vec2::new(100.0, 120.0)));
+    app.world.get_mut::<Wounds>(patient).unwrap().value.push(Wound {bleed_rate: 5.0});
+    app.world.get_mut::<Wounds>(patient).unwrap().clotting_rate = None;
+    set_will(&mut app, patient, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, patient, Will {use_item: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, patient, Will {drop: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, patient, Will {..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32 * 2);
+    assert!(app.world.get::<Parent>(bandage).is_none());
+    assert!(app.world.get::<Healing>(bandage).unwrap().use_timer.is_none());
+    assert_eq!(app.world.get::<Wounds>(patient).unwrap().value.len(), 1);
+}
+
+#[test]
+fn worn_armour_absorbs_bullets_until_it_wears_out() {
+    let mut app = new_app();
+    let torso_shot_angle = std::f32::consts::PI * 0.75; // So that a hit from the left lands between the head and an arm
+    let armoured = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 80.0), torso_shot_angle, 40000.0));
+    let unarmoured = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 160.0), torso_shot_angle, 40000.0));
+    let armour_pickup = spawn(&mut app, |commands| spawn_armour_pickup(commands, Vec2::new(100.0, 80.0)));
+    set_will(&mut app, armoured, Will {pick_up: true, ..default()}); // Alongside the shooters
+    let shooters: Vec<Entity> = spawn_armed_shooters(&mut app, &[80.0, 160.0].map(|y| (Vec2::new(50.0, y), "machine_gun")))
+        .iter().map(|(shooter, _)| *shooter).collect();
+    assert!(app.world.get_entity(armour_pickup).is_none());
+    let starting_durability = app.world.get::<Armour>(armoured).unwrap().durability;
+
+    fire_once(&mut app, &shooters);
+    assert!(app.world.get::<Dead>(unarmoured).is_some());
+    assert!(app.world.get::<Alive>(armoured).is_some());
+    assert!(app.world.get::<Health>(armoured).unwrap().current < 1.0);
+    assert!(app.world.get::<Armour>(armoured).unwrap().durability < starting_durability);
+    assert!(app.world.get::<ContainedBlood>(armoured).unwrap().amount > app.world.get::<ContainedBlood>(unarmoured).unwrap().amount);
+
+    // Keep shooting until the armour's gone
+    *app.world.get_mut::<Health>(armoured).unwrap() = Health {maximum: 100.0, current: 100.0};
+    set_will(&mut app, shooters[0], Will {depress_trigger: true, ..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    assert!(app.world.get::<Armour>(armoured).is_none());
+}
+
+#[test]
+fn deaths_are_attributed_to_their_killer_and_weapon() {
+    let mut app = new_app();
+    let machine_gunned = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(100.0, 80.0), 0.0, 40000.0));
+    let cannoned = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(160.0, 120.0), 0.0, 40000.0));
+    let grenaded = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(130.0, 170.0), 0.0, 40000.0));
+    let mut death_reader = ManualEventReader::<Death>::default();
+    let shooters = spawn_armed_shooters(&mut app, &[
+        (Vec2::new(50.0, 80.0), "machine_gun"),
+        (Vec2::new(100.0, 120.0), "ship_cannon"),
+        (Vec2::new(50.0, 170.0), "grenade_launcher")
+    ]);
+    let [(machine_gunner, machine_gun), (cannoneer, cannon), (grenadier, launcher)] = [shooters[0], shooters[1], shooters[2]];
+
+    fire_once(&mut app, &[machine_gunner, cannoneer, grenadier]);
+    tick(&mut app, (TICKS_PER_SECOND * 0.5) as u32);
+
+    let deaths: Vec<(Entity, Option<Entity>, Option<Entity>, Option<String>, Option<DamageType>, bool)> = death_reader.iter(app.world.resource::<Events<Death>>())
+        .map(|death| (death.entity, death.killer, death.weapon, death.weapon_name.clone(), death.damage_type, death.gibbed))
+        .collect();
+    for victim in [machine_gunned, cannoned, grenaded] {
+        assert_eq!(deaths.iter().filter(|death| death.0 == victim).count(), 1);
+    }
+    assert!(deaths.contains(&(machine_gunned, Some(machine_gunner), Some(machine_gun), Some("machine_gun".to_string()), Some(DamageType::Ballistic), false)));
+    assert!(deaths.contains(&(cannoned, Some(cannoneer), Some(cannon), Some("ship_cannon".to_string()), Some(DamageType::Ballistic), true)));
+    assert!(deaths.contains(&(grenaded, Some(grenadier), Some(launcher), Some("grenade_launcher".to_string()), Some(DamageType::Explosive), true)));
+}
+
+#[test]
+fn hand_grenade_kills_are_still_attributed_after_the_grenade_is_gone() {
+    let mut app = new_app();
+    let grenadier = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let grenade = spawn(&mut app, |commands| spawn_grenade(commands, Vec2::new(60.0, 120.0)));
+    let victim = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(85.0, 120.0), 0.0, 40000.0));
+    let mut death_reader = ManualEventReader::<Death>::default();
+    set_will(&mut app, grenadier, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(grenade).unwrap().get(), grenadier);
+    set_will(&mut app, grenadier, Will {depress_trigger: true, ..default()});
+    tick(&mut app, 1);
+    set_will(&mut app, grenadier, Will {..default()});
+    tick(&mut app, TICKS_PER_SECOND as u32 * 4);
+
+    assert!(app.world.get_entity(grenade).is_none());
+    let death = death_reader.iter(app.world.resource::<Events<Death>>()).find(|death| death.entity == victim).unwrap();
+    assert_eq!(death.killer, Some(grenadier));
+    assert_eq!(death.weapon, Some(grenade)); // Dangling now, hence the name
+    assert_eq!(death.weapon_name, Some("grenade".to_string()));
+}
+
+#[test]
+fn wall_slam_deaths_are_credited_to_whoever_did_the_last_damage() {
+    let mut app = new_app();
+    let attacker = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 80.0), 0.0, 40000.0));
+    let victim = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(140.0, 160.0), 0.0, 4000.0));
+    *app.world.get_mut::<LastDamage>(victim).unwrap() = LastDamage { // As if shot into the wall
+        source: Some(attacker),
+        weapon: None,
+        weapon_name: Some("ship_cannon".to_string()),
+        damage_type: Some(DamageType::Ballistic),
+        time_since: 0.0
+    };
+    app.world.get_mut::<Velocity>(victim).unwrap().value = Vec2::new(3000.0, 0.0);
+    let mut death_reader = ManualEventReader::<Death>::default();
+
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    let death = death_reader.iter(app.world.resource::<Events<Death>>()).find(|death| death.entity == victim).unwrap();
+    assert_eq!(death.killer, Some(attacker));
+    assert_eq!(death.weapon_name, Some("ship_cannon".to_string()));
+    assert_eq!(app.world.get::<LastDamage>(victim).unwrap().source, Some(attacker));
+}
+
+#[test]
+fn wall_slams_long_after_the_last_damage_are_no_ones_doing() {
+    let mut app = new_app();
+    let attacker = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 80.0), 0.0, 40000.0));
+    let victim = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(140.0, 160.0), 0.0, 4000.0));
+    *app.world.get_mut::<LastDamage>(victim).unwrap() = LastDamage { // As if grazed
+        source: Some(attacker),
+        weapon: None,
+        weapon_name: Some("pistol".to_string()),
+        damage_type: Some(DamageType::Ballistic),
+        time_since: 0.0
+    };
+    let mut death_reader = ManualEventReader::<Death>::default();
+
+    tick(&mut app, (TICKS_PER_SECOND * 2.0) as u32);
+    app.world.get_mut::<Velocity>(victim).unwrap().value = Vec2::new(3000.0, 0.0);
+    tick(&mut app, TICKS_PER_SECOND as u32);
+    let death = death_reader.iter(app.world.resource::<Events<Death>>()).find(|death| death.entity == victim).unwrap();
+    assert_eq!(death.killer, None);
+    assert_eq!(death.weapon_name, None);
+    assert_eq!(app.world.get::<LastDamage>(victim).unwrap().source, None);
+}
+
+#[test]
+fn melee_swings_do_not_reach_through_walls() {
+    let mut app = new_app();
+    let batter = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let bat = spawn(&mut app, |commands| spawn_bat(commands, Vec2::new(60.0, 120.0)));
+    let walled_off = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(95.0, 120.0), 0.0, 40000.0)); // In a bat's reach
+    for y in 8..11 {
+        add_wall_tile(&mut app, TilePos {x: 6, y: y}, 2); // Between them
+    }
+    set_will(&mut app, batter, Will {pick_up: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Parent>(bat).unwrap().get(), batter);
+    set_will(&mut app, batter, Will {melee: true, ..default()});
+    tick(&mut app, 1);
+    assert_eq!(app.world.get::<Health>(walled_off).unwrap().current, 1.0);
+    assert_eq!(app.world.get::<Velocity>(walled_off).unwrap().value, Vec2::ZERO);
+}
+
+#[test]
+fn glancing_collisions_spin_both_bodies() {
+    let mut app = new_app();
+    let mover = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(60.0, 120.0), 0.0, 40000.0));
+    let glanced = spawn(&mut app, |commands| spawn_actor(commands, Vec2::new(80.0, 135.0), 0.0, 40000.0)); // Off to one side of the mover's path
+    app.world.get_mut::<Velocity>(mover).unwrap().value = Vec2::new(200.0, 0.0);
+
+    let mut ticks = 0;
+    while app.world.get::<AngularVelocity>(glanced).unwrap().value == 0.0 {
+        tick(&mut app, 1);
+        ticks += 1;
+        assert!(ticks < 10);
+    }
+    // The mover's upper side is dragged backwards and the glanced body's lower side forwards, so both turn anticlockwise
+    assert!(app.world.get::<AngularVelocity>(mover).unwrap().value > 0.0);
+    assert!(app.world.get::<AngularVelocity>(glanced).unwrap().value > 0.0);
+}
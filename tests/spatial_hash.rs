@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+use shootre::resources::*;
+
+#[test]
+fn queries_find_overlapping_entities_only() {
+    let mut spatial_hash = SpatialHash::default();
+    let a = Entity::from_raw(0);
+    let b = Entity::from_raw(1);
+    let c = Entity::from_raw(2);
+    spatial_hash.insert(a, Vec2::new(10.0, 10.0), 5.0);
+    spatial_hash.insert(b, Vec2::new(30.0, 10.0), 5.0); // Straddles the cell boundary at 32
+    spatial_hash.insert(c, Vec2::new(-200.0, -200.0), 5.0);
+
+    assert_eq!(spatial_hash.query_circle(Vec2::new(20.0, 10.0), 1.0), vec![a, b]);
+    assert_eq!(spatial_hash.query_circle(Vec2::new(40.0, 10.0), 1.0), vec![b]);
+    assert_eq!(spatial_hash.query_line(Vec2::new(-210.0, -200.0), Vec2::new(-150.0, -200.0)), vec![c]);
+    assert_eq!(spatial_hash.query_line(Vec2::new(100.0, 10.0), Vec2::new(40.0, 10.0)), vec![b]);
+    assert_eq!(spatial_hash.get_pairs(), vec![(a, b)]);
+
+    spatial_hash.clear();
+    assert!(spatial_hash.get_pairs().is_empty());
+}
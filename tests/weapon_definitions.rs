@@ -0,0 +1,47 @@
+mod common;
+
+use bevy::prelude::*;
+use shootre::PreUpdateSet;
+use shootre::components::*;
+use shootre::resources::*;
+use shootre::systems::weapon_definitions::apply_weapon_definition_changes;
+use common::*;
+
+#[test]
+fn every_weapon_file_parses_and_is_built_in() {
+    let built_in = WeaponDefinitions::default();
+    let mut count = 0;
+    for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/weapons")).unwrap() {
+        let path = entry.unwrap().path();
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        let name = file_name.strip_suffix(".weapon.ron").unwrap();
+        let definition: WeaponDefinition = ron::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(&definition, built_in.get(name));
+        count += 1;
+    }
+    assert_eq!(count, built_in.value.len());
+}
+
+#[test]
+fn staged_weapon_definition_changes_apply_at_the_next_tick_keeping_state() {
+    let mut app = new_app();
+    app
+        .init_resource::<PendingWeaponDefinitions>()
+        .add_system(apply_weapon_definition_changes.in_set(PreUpdateSet::Main).before(PreUpdateSet::CommandFlush).in_schedule(CoreSchedule::FixedUpdate));
+    let machine_gun = spawn_named_weapon(&mut app, "machine_gun", Vec2::new(100.0, 100.0));
+    app.world.get_mut::<Gun>(machine_gun).unwrap().rounds_in_magazine = 7;
+
+    let mut definition = app.world.resource::<WeaponDefinitions>().get("machine_gun").clone();
+    definition.mass = 40.0;
+    definition.magazine_capacity = 50;
+    app.world.resource_mut::<PendingWeaponDefinitions>().value.push(("machine_gun".to_string(), definition));
+    assert_eq!(app.world.get::<Mass>(machine_gun).unwrap().value, 20.0); // Not until a tick runs
+
+    tick(&mut app, 1);
+    assert!(app.world.resource::<PendingWeaponDefinitions>().value.is_empty());
+    assert_eq!(app.world.resource::<WeaponDefinitions>().get("machine_gun").magazine_capacity, 50);
+    assert_eq!(app.world.get::<Mass>(machine_gun).unwrap().value, 40.0);
+    let gun = app.world.get::<Gun>(machine_gun).unwrap();
+    assert_eq!(gun.magazine_capacity, 50);
+    assert_eq!(gun.rounds_in_magazine, 7);
+}